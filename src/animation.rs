@@ -0,0 +1,543 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
+
+use nannou::rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::lsystem::{Circle, LsystemTree, Polygon, ShapeInstance, Stroke};
+
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+pub struct BranchInfo {
+    pub id: usize,
+    pub i_on_start: usize,
+}
+
+impl BranchInfo {
+    pub fn new(id: usize, i_on_start: usize) -> Self {
+        Self { id, i_on_start }
+    }
+
+    pub fn main_branch() -> Self {
+        Self {
+            id: 0,
+            i_on_start: 0,
+        }
+    }
+}
+
+// in which order the found branches start to grow
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Scheduling {
+    // the queued branches first, then the last found ones, with the limit of growing branches
+    Queue,
+    // the branches with the smaller branch order first
+    BreadthFirst,
+    // the deepest and the last found branches first
+    DepthFirst,
+    // every branch starts when its fork is reached and grows by its real length, so the tree
+    // grows by the arc length from the root (without the limit of growing branches)
+    ArcLength,
+    // a random waiting branch, seeded so that the animation is the same every time
+    Random,
+}
+
+// the branches, that are found, but wait for a free place to grow
+struct WaitingBranches {
+    scheduling: Scheduling,
+    queue: VecDeque<BranchInfo>,
+    // (branch order, found progress, id), the biggest is taken first
+    deepest: BinaryHeap<(usize, usize, usize)>,
+    lowest: BinaryHeap<Reverse<(usize, usize, usize)>>,
+    random: Vec<BranchInfo>,
+    rng: StdRng,
+}
+
+impl WaitingBranches {
+    fn new(scheduling: Scheduling) -> Self {
+        Self {
+            scheduling,
+            queue: VecDeque::new(),
+            deepest: BinaryHeap::new(),
+            lowest: BinaryHeap::new(),
+            random: vec![],
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len() + self.deepest.len() + self.lowest.len() + self.random.len()
+    }
+
+    // puts the branches found in one step in the waiting branches
+    fn push_found(&mut self, tree: &LsystemTree, mut found: Vec<BranchInfo>) {
+        match self.scheduling {
+            // the last found ones come first, but after the already queued branches
+            Scheduling::Queue | Scheduling::ArcLength => {
+                while let Some(branch_info) = found.pop() {
+                    self.queue.push_back(branch_info);
+                }
+            }
+            Scheduling::BreadthFirst => {
+                for branch_info in found {
+                    let order = tree.branch_order(&branch_info.id);
+                    self.lowest
+                        .push(Reverse((order, branch_info.i_on_start, branch_info.id)));
+                }
+            }
+            Scheduling::DepthFirst => {
+                for branch_info in found {
+                    let order = tree.branch_order(&branch_info.id);
+                    self.deepest
+                        .push((order, branch_info.i_on_start, branch_info.id));
+                }
+            }
+            Scheduling::Random => self.random.extend(found),
+        }
+    }
+
+    fn pop(&mut self) -> Option<BranchInfo> {
+        match self.scheduling {
+            Scheduling::Queue | Scheduling::ArcLength => self.queue.pop_front(),
+            Scheduling::BreadthFirst => self
+                .lowest
+                .pop()
+                .map(|Reverse((_, found, id))| BranchInfo::new(id, found)),
+            Scheduling::DepthFirst => self
+                .deepest
+                .pop()
+                .map(|(_, found, id)| BranchInfo::new(id, found)),
+            Scheduling::Random => {
+                if self.random.is_empty() {
+                    return None;
+                }
+                let i = self.rng.gen_range(0..self.random.len());
+                Some(self.random.swap_remove(i))
+            }
+        }
+    }
+}
+
+// the growing plan of one tree, used by the viewer and by the offline frames export.
+// The scheduling of the branches is simulated once dot by dot, so the result doesn't depend on
+// the frame rate and the state can be taken for any progress (in dots from the start)
+pub struct TreeAnimation {
+    // the progress, on which the branch starts to grow
+    starts: HashMap<usize, usize>,
+    // the progress, on which the fork of the branch is reached and the branch is waiting to grow
+    founds: HashMap<usize, usize>,
+    // the progress, on which the branch is fully grown, with the id of the branch sorted by it
+    ends: Vec<(f32, usize)>,
+    // the ids of the ends in the same order, the grown branches of any progress are its start
+    grown_ids: Vec<usize>,
+    // the progress, on which all branches are grown
+    duration: usize,
+    // the progress, on which every point of the stroke of the branch is reached, only for the
+    // arc length scheduling, where the branches grow by their real length and not by the dots
+    arc_steps: HashMap<usize, Vec<f32>>,
+}
+
+impl TreeAnimation {
+    pub fn new(tree: &LsystemTree, max_branches: usize, scheduling: Scheduling) -> Self {
+        if let Scheduling::ArcLength = scheduling {
+            return Self::by_arc_length(tree);
+        }
+        // with more trees than MAX_ANIMATED_BRANCHES every tree still grows one branch at a time
+        let max_branches = max_branches.max(1);
+
+        let mut starts = HashMap::from([(0, 0)]);
+        let mut founds = HashMap::from([(0, 0)]);
+        let mut branches_to_animate = vec![BranchInfo::main_branch()];
+        let mut waiting_branches = WaitingBranches::new(scheduling);
+        let mut progress_i = 0;
+
+        while !branches_to_animate.is_empty() || waiting_branches.len() != 0 {
+            progress_i += 1;
+
+            let mut new_founded_branches = vec![];
+            branches_to_animate.retain(|branch_info| {
+                // updating the new opened branches to animate, if there are some
+                if let Some(branch_dots) = tree.branches.get(&branch_info.id) {
+                    // getting the index in dependence from the index on the start of drawing, but not
+                    // bigger as the len of the dots itself
+                    let to_index = (progress_i - branch_info.i_on_start).min(branch_dots.len());
+                    if to_index == 0 {
+                        return false;
+                    }
+                    // checking if the new drawed dot has some connected branches
+                    for &branch_id in &branch_dots[to_index - 1].connected_branches_id {
+                        // and push new branches in the branches to temp branches
+                        new_founded_branches.push(BranchInfo::new(branch_id, progress_i));
+                        founds.insert(branch_id, progress_i);
+                    }
+                    to_index != branch_dots.len()
+                } else {
+                    false
+                }
+            });
+            waiting_branches.push_found(tree, new_founded_branches);
+
+            // we render only some number of branches at the time (optimisation)
+            while branches_to_animate.len() < max_branches {
+                let Some(mut branch_info) = waiting_branches.pop() else {
+                    break;
+                };
+                // we change the start i of them
+                branch_info.i_on_start = progress_i;
+                starts.insert(branch_info.id, branch_info.i_on_start);
+                branches_to_animate.push(branch_info);
+            }
+        }
+
+        let ends = starts
+            .iter()
+            .map(|(id, start)| {
+                let len = tree.branches.get(id).map_or(0, |branch| branch.len());
+                ((start + len) as f32, *id)
+            })
+            .collect();
+
+        Self::with_ends(starts, founds, ends, progress_i, HashMap::new())
+    }
+
+    // every branch starts when its fork is reached and grows by the distance along the branches,
+    // one progress is the mean length of the segments, so the speed is like with the other
+    // schedulings
+    fn by_arc_length(tree: &LsystemTree) -> Self {
+        // the distance from the root to the fork of every branch
+        let mut fork_arcs = HashMap::from([(0, 0.0)]);
+        for branch in tree.branches.values() {
+            for dot in branch {
+                for id in dot.connected_branches_id.iter() {
+                    fork_arcs.insert(*id, dot.arc_length);
+                }
+            }
+        }
+
+        let mut arcs = HashMap::new();
+        let mut length = 0.0;
+        let mut segments = 0;
+        for (id, branch) in tree.branches.iter() {
+            let fork_arc = fork_arcs.get(id).cloned().unwrap_or(0.0);
+            let mut branch_arcs = vec![];
+            // the stroke starts with the fork dot, if there is one
+            if tree.branches_start.contains_key(id) {
+                branch_arcs.push(fork_arc);
+            }
+            branch_arcs.extend(branch.iter().map(|dot| dot.arc_length));
+            if let Some(last) = branch.last() {
+                length += last.arc_length - fork_arc;
+                segments += branch.len();
+            }
+            arcs.insert(*id, branch_arcs);
+        }
+        let step = if length > f32::EPSILON {
+            length / segments as f32
+        } else {
+            1.0
+        };
+
+        let mut starts = HashMap::new();
+        let mut ends = vec![];
+        let mut arc_steps = HashMap::new();
+        for (id, branch_arcs) in arcs {
+            let steps = branch_arcs
+                .iter()
+                .map(|arc| arc / step)
+                .collect::<Vec<f32>>();
+            if let (Some(first), Some(last)) = (steps.first(), steps.last()) {
+                starts.insert(id, first.floor() as usize);
+                ends.push((*last, id));
+            }
+            arc_steps.insert(id, steps);
+        }
+        let duration = ends
+            .iter()
+            .map(|(end, _)| end.ceil() as usize)
+            .max()
+            .unwrap_or(0);
+
+        // nothing is waiting, every branch starts as soon as it is found
+        Self::with_ends(starts.clone(), starts, ends, duration, arc_steps)
+    }
+
+    fn with_ends(
+        starts: HashMap<usize, usize>,
+        founds: HashMap<usize, usize>,
+        mut ends: Vec<(f32, usize)>,
+        duration: usize,
+        arc_steps: HashMap<usize, Vec<f32>>,
+    ) -> Self {
+        ends.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let grown_ids = ends.iter().map(|(_, id)| *id).collect();
+        Self {
+            starts,
+            founds,
+            ends,
+            grown_ids,
+            duration,
+            arc_steps,
+        }
+    }
+
+    // how many segments of the stroke of the branch are grown at the progress, the last one can
+    // be grown partly
+    fn grown_segments(&self, id: usize, start: usize, progress: f32) -> f32 {
+        let Some(steps) = self.arc_steps.get(&id) else {
+            return progress - start as f32;
+        };
+        let reached = steps.partition_point(|step| *step <= progress);
+        if reached == 0 {
+            return progress - steps[0];
+        }
+        if reached == steps.len() {
+            return (steps.len() - 1) as f32 + progress - steps[steps.len() - 1];
+        }
+        let (from, to) = (steps[reached - 1], steps[reached]);
+        (reached - 1) as f32 + (progress - from) / (to - from)
+    }
+
+    // the progress in dots, on which the whole tree is grown
+    pub fn duration(&self) -> usize {
+        self.duration
+    }
+
+    // how many branches are fully grown at the progress
+    fn grown_count_at(&self, progress: f32) -> usize {
+        self.ends.partition_point(|(end, _)| *end <= progress)
+    }
+
+    // the ids of the branches, that are not fully grown at the progress, so the grown ones don't
+    // make the frames slower
+    fn unfinished_at(&self, progress: f32) -> &[usize] {
+        &self.grown_ids[self.grown_count_at(progress)..]
+    }
+
+    // the branches, that are growing at the progress, as the scheduling had them at this moment
+    pub fn branches_to_animate_at(&self, tree: &LsystemTree, progress: f32) -> Vec<BranchInfo> {
+        let mut res = vec![];
+        for id in self.unfinished_at(progress) {
+            let start = self.starts[id];
+            let grown = self.grown_segments(*id, start, progress);
+            let len = tree.branches.get(id).map_or(0, |branch| branch.len());
+            if grown > 0.0 && grown < len as f32 {
+                res.push(BranchInfo::new(*id, start));
+            }
+        }
+        res
+    }
+
+    // the branches, which forks are already reached, but that are waiting to grow at the
+    // progress, the i_on_start is the moment when they were found
+    pub fn queued_branches_at(&self, progress: f32) -> Vec<BranchInfo> {
+        let mut res = vec![];
+        for id in self.unfinished_at(progress) {
+            let (Some(found), Some(start)) = (self.founds.get(id), self.starts.get(id)) else {
+                continue;
+            };
+            if (*found as f32) <= progress && progress < *start as f32 {
+                res.push(BranchInfo::new(*id, *found));
+            }
+        }
+        res
+    }
+
+    // the branches, that are fully grown at the progress, in the order they were finished, the
+    // ones finished since the last frame are the end of it
+    pub fn grown_branches_at(&self, progress: f32) -> &[usize] {
+        &self.grown_ids[..self.grown_count_at(progress)]
+    }
+
+    // the progress, on which the branch has grown to the i-th dot, None if the branch never grows
+    fn dot_start(&self, branch_id: usize, i_on_branch: usize) -> Option<f32> {
+        if let Some(steps) = self.arc_steps.get(&branch_id) {
+            return steps.get(i_on_branch).or(steps.last()).cloned();
+        }
+        self.starts
+            .get(&branch_id)
+            .map(|start| (start + i_on_branch) as f32)
+    }
+
+    // the progress, on which the branch has grown to the circle
+    pub fn circle_start(&self, circle: &Circle) -> Option<f32> {
+        self.dot_start(circle.branch_id, circle.i_on_branch)
+    }
+
+    pub fn polygon_start(&self, polygon: &Polygon) -> Option<f32> {
+        self.dot_start(polygon.branch_id, polygon.i_on_branch)
+    }
+
+    pub fn shape_start(&self, instance: &ShapeInstance) -> Option<f32> {
+        self.dot_start(instance.branch_id, instance.i_on_branch)
+    }
+
+    // the circles, that are already drawn at the progress
+    pub fn visible_circles<'a>(&self, tree: &'a LsystemTree, progress: f32) -> Vec<&'a Circle> {
+        tree.circles
+            .iter()
+            .filter(|circle| {
+                self.circle_start(circle)
+                    .is_some_and(|start| start <= progress)
+            })
+            .collect()
+    }
+
+    // the polygons, that are already drawn at the progress
+    pub fn visible_polygons<'a>(&self, tree: &'a LsystemTree, progress: f32) -> Vec<&'a Polygon> {
+        tree.polygons
+            .iter()
+            .filter(|polygon| {
+                self.polygon_start(polygon)
+                    .is_some_and(|start| start <= progress)
+            })
+            .collect()
+    }
+
+    // the shapes, that are already stamped at the progress
+    pub fn visible_shapes<'a>(
+        &self,
+        tree: &'a LsystemTree,
+        progress: f32,
+    ) -> Vec<&'a ShapeInstance> {
+        tree.shape_instances
+            .iter()
+            .filter(|instance| {
+                self.shape_start(instance)
+                    .is_some_and(|start| start <= progress)
+            })
+            .collect()
+    }
+
+    // the already grown part of every growing branch with its id, the progress can be fractional,
+    // then the last segment is drawn partly
+    pub fn visible_branches(&self, tree: &LsystemTree, progress: f32) -> Vec<(usize, Stroke)> {
+        self.branches_to_animate_at(tree, progress)
+            .iter()
+            .map(|branch_info| {
+                let grown = self.grown_segments(branch_info.id, branch_info.i_on_start, progress);
+                let stroke = tree.branch_stroke(&branch_info.id).grown_part(grown);
+                (branch_info.id, stroke)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::AppConfig,
+        lsystem::{Behaviour, LsystemBuilder, Rule},
+    };
+
+    const SCHEDULINGS: [Scheduling; 5] = [
+        Scheduling::Queue,
+        Scheduling::BreadthFirst,
+        Scheduling::DepthFirst,
+        Scheduling::ArcLength,
+        Scheduling::Random,
+    ];
+
+    fn tree(axiom: &str, deep: usize) -> LsystemTree {
+        let mut app_config = AppConfig::new(vec![deep]);
+        app_config.config.axiom = axiom.to_string();
+        // a step four times longer than the one of F
+        app_config
+            .config
+            .rules
+            .insert(Rule::new('G', "G", Behaviour::DrawForward));
+        let step = app_config.config.start_direction.length();
+        app_config.config.symbol_steps.insert('G', 4.0 * step);
+        LsystemBuilder::new(&app_config.config)
+            .build_tree_3d(&deep)
+            .tree
+    }
+
+    #[test]
+    fn every_scheduling_grows_all_branches() {
+        let tree = tree("X", 3);
+        for scheduling in SCHEDULINGS {
+            let animation = TreeAnimation::new(&tree, 4, scheduling);
+            let mut grown = animation
+                .grown_branches_at(animation.duration() as f32)
+                .to_vec();
+            grown.sort();
+            let mut ids = tree.branches.keys().cloned().collect::<Vec<usize>>();
+            ids.sort();
+            assert_eq!(grown, ids, "{:?}", scheduling);
+            assert!(animation
+                .branches_to_animate_at(&tree, animation.duration() as f32)
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn only_the_limit_of_branches_grows_at_once() {
+        let tree = tree("X", 3);
+        for scheduling in [
+            Scheduling::Queue,
+            Scheduling::BreadthFirst,
+            Scheduling::DepthFirst,
+        ] {
+            let animation = TreeAnimation::new(&tree, 2, scheduling);
+            for progress in 0..animation.duration() {
+                let growing = animation.branches_to_animate_at(&tree, progress as f32 + 0.5);
+                assert!(growing.len() <= 2, "{:?} at {}", scheduling, progress);
+            }
+        }
+    }
+
+    #[test]
+    fn without_a_limit_one_branch_grows_at_once() {
+        let tree = tree("X", 2);
+        let animation = TreeAnimation::new(&tree, 0, Scheduling::Queue);
+        assert_eq!(
+            animation
+                .grown_branches_at(animation.duration() as f32)
+                .len(),
+            tree.branches.len()
+        );
+    }
+
+    #[test]
+    fn breadth_first_grows_the_lower_orders_first() {
+        let tree = tree("X", 3);
+        let animation = TreeAnimation::new(&tree, 1, Scheduling::BreadthFirst);
+        let mut starts = animation
+            .starts
+            .iter()
+            .map(|(id, start)| (*start, tree.branch_order(id)))
+            .collect::<Vec<(usize, usize)>>();
+        starts.sort();
+        assert!(starts.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn random_is_the_same_every_time() {
+        let tree = tree("X", 3);
+        let first = TreeAnimation::new(&tree, 2, Scheduling::Random);
+        let second = TreeAnimation::new(&tree, 2, Scheduling::Random);
+        assert_eq!(first.ends, second.ends);
+    }
+
+    #[test]
+    fn arc_length_grows_by_the_distance() {
+        // the main branch ends on the distance 5, the side branch on 8 (4 + 4 times 1)
+        let tree = tree("G[+FFFF]F", 0);
+        let animation = TreeAnimation::new(&tree, 1, Scheduling::ArcLength);
+        let end = |id| {
+            animation
+                .ends
+                .iter()
+                .find(|(_, end_id)| *end_id == id)
+                .map(|(end, _)| *end)
+                .unwrap()
+        };
+        let side = *tree.branches.keys().find(|id| **id != 0).unwrap();
+        assert!((end(side) / end(0) - 8.0 / 5.0).abs() < 1e-3);
+        // the side branch starts on its fork, not when its fork dot is counted
+        let grown = animation.grown_segments(side, animation.starts[&side], end(0));
+        assert!((grown - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,158 @@
+use std::f32::consts::FRAC_PI_2;
+
+use nannou::{glam::Quat, prelude::*};
+
+use crate::lsystem::{LsystemTree, LsystemTree3D};
+
+// how many radians the camera turns for one pixel of the mouse drag
+const ORBIT_SPEED: f32 = 0.01;
+// the distance of the eye from the pivot for the perspective projection
+const FOCAL_LENGTH: f32 = 800.0;
+// how much the farthest dots are faded to the background
+const FADE_STRENGTH: f32 = 0.7;
+
+// the camera of the viewer, that orbits around the pivot with the mouse drag, without turning it
+// looks at the trees from the front, so the 2D trees look as before. P toggles the perspective,
+// F the fading by the depth and O resets the camera
+pub struct OrbitCamera {
+    pivot: Point3,
+    yaw: f32,
+    pitch: f32,
+    pub perspective: bool,
+    pub fade: bool,
+    // the last mouse position of the drag
+    dragging: Option<Point2>,
+    // the trees have to be projected again
+    changed: bool,
+}
+
+// everything, that the projected trees depend on, the trees built with another projection have
+// to be projected again
+#[derive(Clone, Copy, PartialEq)]
+pub struct Projection {
+    pivot: Point3,
+    yaw: f32,
+    pitch: f32,
+    perspective: bool,
+    fade: bool,
+    // the factor of the widths, that keeps them on the screen while zooming
+    width_scale: f32,
+    // the color, to which the far dots are faded
+    bg: Rgb,
+}
+
+impl Projection {
+    // the point on the screen in x and y and its depth in z, the bigger z is nearer
+    pub fn project_point(&self, point: Point3) -> Point3 {
+        let rotation = Quat::from_rotation_x(self.pitch) * Quat::from_rotation_y(self.yaw);
+        let rotated = rotation * (point - self.pivot);
+        let factor = if self.perspective {
+            FOCAL_LENGTH / (FOCAL_LENGTH - rotated.z).max(1.0)
+        } else {
+            1.0
+        };
+        let screen = self.pivot.truncate() + rotated.truncate() * factor;
+        screen.extend(rotated.z)
+    }
+
+    // the tree of the turtle seen by the camera
+    pub fn project(&self, tree_3d: &LsystemTree3D) -> LsystemTree {
+        let mut tree = tree_3d.project(|point| self.project_point(point));
+        tree.scale_widths(self.width_scale);
+        if self.fade {
+            tree.fade_by_depth(self.bg, FADE_STRENGTH);
+        }
+        tree
+    }
+}
+
+impl OrbitCamera {
+    pub fn new(pivot: Point3) -> OrbitCamera {
+        OrbitCamera {
+            pivot,
+            yaw: 0.0,
+            pitch: 0.0,
+            perspective: false,
+            fade: false,
+            dragging: None,
+            changed: false,
+        }
+    }
+
+    // the snapshot of the camera, that projects the trees also on the background thread
+    pub fn projection(&self, width_scale: f32, bg: Rgb) -> Projection {
+        Projection {
+            pivot: self.pivot,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            perspective: self.perspective,
+            fade: self.fade,
+            width_scale,
+            bg,
+        }
+    }
+
+    // the point on the plane of the trees (z = 0), that is projected on the point of the screen
+    pub fn unproject(&self, screen: Point2) -> Point2 {
+        let inverse =
+            (Quat::from_rotation_x(self.pitch) * Quat::from_rotation_y(self.yaw)).inverse();
+        // the ray of the screen point before the rotation, the depth goes along it
+        let on_screen = (screen - self.pivot.truncate()).extend(0.0);
+        let direction = if self.perspective {
+            vec3(
+                -on_screen.x / FOCAL_LENGTH,
+                -on_screen.y / FOCAL_LENGTH,
+                1.0,
+            )
+        } else {
+            Vec3::Z
+        };
+        let origin = self.pivot + inverse * on_screen;
+        let direction = inverse * direction;
+        // looking along the plane, the point stays on the depth of the pivot
+        if direction.z.abs() < 1e-3 {
+            return origin.truncate();
+        }
+        (origin - direction * (origin.z / direction.z)).truncate()
+    }
+
+    // returns true once after every change of the camera
+    pub fn take_changed(&mut self) -> bool {
+        let changed = self.changed;
+        self.changed = false;
+        changed
+    }
+
+    // the trees are new, so they have to be projected
+    pub fn refresh(&mut self) {
+        self.changed = true;
+    }
+
+    pub fn key_pressed(&mut self, key: Key) {
+        match key {
+            Key::P => self.perspective = !self.perspective,
+            Key::F => self.fade = !self.fade,
+            Key::O => *self = OrbitCamera::new(self.pivot),
+            _ => return,
+        }
+        self.changed = true;
+    }
+
+    pub fn mouse_pressed(&mut self, mouse: Point2) {
+        self.dragging = Some(mouse);
+    }
+
+    pub fn mouse_moved(&mut self, mouse: Point2) {
+        if let Some(last) = self.dragging {
+            let delta = mouse - last;
+            self.yaw += delta.x * ORBIT_SPEED;
+            self.pitch = (self.pitch - delta.y * ORBIT_SPEED).clamp(-FRAC_PI_2, FRAC_PI_2);
+            self.dragging = Some(mouse);
+            self.changed = true;
+        }
+    }
+
+    pub fn mouse_released(&mut self) {
+        self.dragging = None;
+    }
+}
@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+// the command line arguments: trees_bg [--config file] [output file]
+pub struct Cli {
+    // if given, the trees are exported in this file instead of opening the window,
+    // the format is chosen by the extension (.dxf, .pdf, .svg, .obj, .glb, .gif or .png for the
+    // growth animation)
+    pub output: Option<PathBuf>,
+    // the config file, the viewer loads it again after every change
+    pub config: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn parse() -> Cli {
+        let mut cli = Cli {
+            output: None,
+            config: None,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--config" {
+                cli.config = args.next().map(PathBuf::from);
+            } else {
+                cli.output = Some(PathBuf::from(arg));
+            }
+        }
+        cli
+    }
+}
@@ -1,8 +1,11 @@
+use std::f32::consts::PI;
+
 use nannou::{geom::Point2, glam::Vec2};
 
 use crate::{
+    animation::Scheduling,
     constants::*,
-    lsystem::{Behaviour, LsystemConfig, Rule, Rules},
+    lsystem::{Behaviour, ColorScheme, Jitter, LsystemConfig, Rule, Rules, Shape, ShapeSource},
 };
 
 const DEGREES_IN_RAD: f32 = 0.01745329;
@@ -10,13 +13,33 @@ const DEGREES_IN_RAD: f32 = 0.01745329;
 #[derive(Clone)]
 pub struct AppConfig {
     pub bg_color: String,
-    pub dots_pro_second: usize,
+    pub dots_pro_second: f32,
+    pub scheduling: Scheduling,
+    pub mesh_segments: usize,
 
     pub start_point: Option<Point2>,
     pub start_point_delta: Option<Point2>,
 
     pub deeps: Vec<usize>,
     pub config: LsystemConfig,
+    // the color schemes of the trees in the order of the deeps, the trees without one use the
+    // color scheme of the config
+    pub color_schemes: Vec<ColorScheme>,
+
+    // the trees planted by the clicks, after the trees of the deeps
+    pub planted: Vec<Planted>,
+    // the preset and the depth of the next planted tree
+    pub plant_preset: usize,
+    pub plant_deep: usize,
+}
+
+// a tree planted by the click, it starts to grow at the progress of the click
+#[derive(Clone)]
+pub struct Planted {
+    pub point: Point2,
+    pub preset: String,
+    pub deep: usize,
+    pub start: f32,
 }
 
 impl AppConfig {
@@ -28,51 +51,113 @@ impl AppConfig {
             Rule::new('F', "FF", Behaviour::DrawForward),
             Rule::new('+', "+", Behaviour::RotateLeft),
             Rule::new('-', "-", Behaviour::RotateRight),
+            // the turns with their own angle from SYMBOL_ANGLES
+            Rule::new('>', ">", Behaviour::RotateLeft),
+            Rule::new('<', "<", Behaviour::RotateRight),
             Rule::new('[', "[", Behaviour::Branch),
             Rule::new(']', "]", Behaviour::BranchStop),
+            // the other turtle symbols, as in "The Algorithmic Beauty of Plants"
+            Rule::new('f', "f", Behaviour::MoveForward),
+            Rule::new('|', "|", Behaviour::TurnAround),
+            Rule::new('!', "!", Behaviour::DecrementWidth(0.2)),
+            Rule::new('#', "#", Behaviour::IncrementWidth(0.2)),
+            Rule::new('\'', "'", Behaviour::NextColor),
+            Rule::new('"', "\"", Behaviour::ScaleStep(0.9)),
+            Rule::new('@', "@", Behaviour::DrawDot(2.0)),
+            Rule::new('{', "{", Behaviour::StartPolygon),
+            Rule::new('.', ".", Behaviour::PolygonVertex),
+            Rule::new('}', "}", Behaviour::EndPolygon),
+            Rule::new('L', "L", Behaviour::StampShape("leaf".to_string())),
+            // the 3D turtle
+            Rule::new('&', "&", Behaviour::PitchDown),
+            Rule::new('^', "^", Behaviour::PitchUp),
+            Rule::new('\\', "\\", Behaviour::RollLeft),
+            Rule::new('/', "/", Behaviour::RollRight),
         ];
 
-        let wind_power = if let Some(temp) = WIND_POWER {
-            Some(Vec2::from(temp))
-        } else {
-            None
-        };
+        let wind_power = WIND_POWER.map(Vec2::from);
+
+        let tropism = TROPISM.map(Vec2::from);
 
         let config = LsystemConfig {
             axiom: AXIOM.to_string(),
             rules: Rules::new(rules),
 
             main_color: FG_COLOR.to_string(),
+            color_scheme: ColorScheme::new(COLOR_MODE, PALETTE),
             line_weight: LINE_WEIGHT,
+            width_mode: WIDTH_MODE,
 
             start_direction: Vec2::from(START_DIRECTION),
-            rotation_factor: ROTATION_DEGREES * DEGREES_IN_RAD,
+            rotation_factor: match ANGLE_DIVISOR {
+                Some(divisor) => 2.0 * PI / divisor as f32,
+                None => ROTATION_DEGREES * DEGREES_IN_RAD,
+            },
+            symbol_angles: SYMBOL_ANGLES
+                .iter()
+                .map(|(ch, degrees)| (*ch, degrees * DEGREES_IN_RAD))
+                .collect(),
+            symbol_steps: SYMBOL_STEPS.iter().cloned().collect(),
 
             wind_power,
+            tropism,
+            susceptibility: SUSCEPTIBILITY,
+            shapes: SHAPES
+                .iter()
+                .map(|(name, path)| Shape::new(name, ShapeSource::SvgPath(path.to_string())))
+                .collect(),
+            jitter: Jitter {
+                angle: ANGLE_JITTER_DEGREES * DEGREES_IN_RAD,
+                length: LENGTH_JITTER,
+                distribution: JITTER_DISTRIBUTION,
+                seed: JITTER_SEED,
+            },
 
             scale_delta: SCALE_DELTA,
             scale_start: SCALE_START,
             scale_min: SCALE_MIN,
         };
 
-        let start_point = if let Some(temp) = START_POINT {
-            Some(Vec2::from(temp))
-        } else {
-            None
-        };
-        let start_point_delta = if let Some(temp) = START_POINT_DELTA {
-            Some(Vec2::from(temp))
-        } else {
-            None
-        };
+        let start_point = START_POINT.map(Vec2::from);
+        let start_point_delta = START_POINT_DELTA.map(Vec2::from);
 
         AppConfig {
             bg_color: BG_COLOR.to_string(),
             dots_pro_second: DOTS_PRO_SECOND,
+            scheduling: SCHEDULING,
+            mesh_segments: MESH_SEGMENTS,
             start_point,
             start_point_delta,
             deeps,
             config,
+            color_schemes: TREE_COLOR_SCHEMES
+                .iter()
+                .map(|(mode, palette)| ColorScheme::new(*mode, palette))
+                .collect(),
+            planted: vec![],
+            plant_preset: 0,
+            plant_deep: PLANT_DEEP,
+        }
+    }
+
+    // the config with the grammar of the preset of the planted tree, None for an unknown preset
+    pub fn planted_config(&self, planted: &Planted) -> Option<LsystemConfig> {
+        self.preset_config(&planted.preset)
+    }
+
+    // the config with the grammar of the preset, None for an unknown preset
+    pub fn preset_config(&self, preset: &str) -> Option<LsystemConfig> {
+        let (_, axiom, rules) = PRESETS.iter().find(|(name, _, _)| *name == preset)?;
+        let mut config = self.config.clone();
+        config.axiom = axiom.to_string();
+        for (ch, text) in rules.iter() {
+            let behaviour = config
+                .rules
+                .get_behaviour(ch)
+                .cloned()
+                .unwrap_or(Behaviour::DrawForward);
+            config.rules.insert(Rule::new(*ch, text, behaviour));
         }
+        Some(config)
     }
 }
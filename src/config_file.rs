@@ -0,0 +1,795 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use nannou::glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::Scheduling,
+    config::{AppConfig, Planted},
+    constants::{DEEPS, MAX_SYMBOLS, PRESETS},
+    lsystem::{
+        Behaviour, ColorMode, ColorScheme, Distribution, LsystemConfig, Rule, Shape, ShapeSource,
+        WidthMode,
+    },
+    misc::is_hex_color,
+};
+
+// how often the config file is checked for changes
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+// the settings of the config file (toml, json, yaml and the other formats of the config crate),
+// every missing one keeps the value of the constants
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct ConfigFile {
+    deeps: Option<Vec<usize>>,
+    axiom: Option<String>,
+    // the rules are added to the default ones, a rule of the same symbol replaces it
+    rules: Vec<RuleFile>,
+    shapes: Vec<ShapeFile>,
+
+    rotation_degrees: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    angle_divisor: Option<u32>,
+    // the symbols with their own rotation in degrees and their own step length
+    symbol_angles: BTreeMap<String, f32>,
+    symbol_steps: BTreeMap<String, f32>,
+    start_direction: Option<(f32, f32)>,
+    scale_start: Option<f32>,
+    scale_delta: Option<f32>,
+    scale_min: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tropism: Option<(f32, f32)>,
+    susceptibility: Option<f32>,
+    jitter: Option<JitterFile>,
+
+    line_weight: Option<f32>,
+    width_mode: Option<WidthMode>,
+    bg_color: Option<String>,
+    main_color: Option<String>,
+    color_mode: Option<ColorMode>,
+    palette: Option<Vec<String>>,
+
+    dots_pro_second: Option<f32>,
+    scheduling: Option<Scheduling>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wind_power: Option<(f32, f32)>,
+
+    // the own color schemes of the trees in the order of the deeps
+    color_schemes: Option<Vec<ColorScheme>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_point: Option<(f32, f32)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_point_delta: Option<(f32, f32)>,
+    mesh_segments: Option<usize>,
+
+    // the trees planted by the clicks, so the saved config is the whole scene, without them the
+    // reloaded config keeps the planted trees of the viewer
+    planted: Option<Vec<PlantedFile>>,
+    // the preset and the depth of the next planted tree
+    plant_preset: Option<String>,
+    plant_deep: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RuleFile {
+    // one char, the config formats have no chars
+    symbol: String,
+    to: String,
+    behaviour: Behaviour,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ShapeFile {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    points: Option<Vec<(f32, f32)>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JitterFile {
+    #[serde(default)]
+    angle_degrees: f32,
+    #[serde(default)]
+    length: f32,
+    #[serde(default = "uniform")]
+    distribution: Distribution,
+    #[serde(default)]
+    seed: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PlantedFile {
+    point: (f32, f32),
+    preset: String,
+    deep: usize,
+    // the progress in dots, on which the tree starts to grow
+    #[serde(default)]
+    start: f32,
+}
+
+fn uniform() -> Distribution {
+    Distribution::Uniform
+}
+
+// the app config with the settings of the file, the errors are the messages for the user
+pub fn load(path: &Path) -> Result<AppConfig, String> {
+    load_over(path, None)
+}
+
+// the app config of the changed file, the trees planted in the viewer and the preset and the
+// depth of the next one stay, if the file doesn't have them
+pub fn reload(path: &Path, current: &AppConfig) -> Result<AppConfig, String> {
+    load_over(path, Some(current))
+}
+
+fn load_over(path: &Path, current: Option<&AppConfig>) -> Result<AppConfig, String> {
+    let file = ::config::Config::builder()
+        .add_source(::config::File::from(path))
+        .build()
+        .and_then(|config| config.try_deserialize::<ConfigFile>())
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+
+    let mut app_config = AppConfig::new(file.deeps.clone().unwrap_or_else(|| DEEPS.to_vec()));
+    if let Some(current) = current {
+        app_config.planted = current.planted.clone();
+        app_config.plant_preset = current.plant_preset;
+        app_config.plant_deep = current.plant_deep;
+    }
+    apply(file, &mut app_config)?;
+    validate(&app_config)?;
+    Ok(app_config)
+}
+
+fn apply(file: ConfigFile, app_config: &mut AppConfig) -> Result<(), String> {
+    let config = &mut app_config.config;
+    if let Some(axiom) = file.axiom {
+        config.axiom = axiom;
+    }
+    for rule in file.rules {
+        let ch = one_char(&rule.symbol)?;
+        config.rules.insert(Rule::new(ch, &rule.to, rule.behaviour));
+    }
+    for shape in file.shapes {
+        let source = match (shape.path, shape.points) {
+            (Some(path), None) => ShapeSource::SvgPath(path),
+            (None, Some(points)) => ShapeSource::Points(points),
+            _ => {
+                return Err(format!(
+                    "The shape {} needs either a path or points",
+                    shape.name
+                ))
+            }
+        };
+        config.shapes.retain(|other| other.name != shape.name);
+        config.shapes.push(Shape::new(&shape.name, source));
+    }
+
+    if let Some(divisor) = file.angle_divisor {
+        config.rotation_factor = 2.0 * std::f32::consts::PI / divisor.max(1) as f32;
+    } else if let Some(degrees) = file.rotation_degrees {
+        config.rotation_factor = degrees.to_radians();
+    }
+    for (symbol, degrees) in file.symbol_angles {
+        config
+            .symbol_angles
+            .insert(one_char(&symbol)?, degrees.to_radians());
+    }
+    for (symbol, step) in file.symbol_steps {
+        config.symbol_steps.insert(one_char(&symbol)?, step);
+    }
+    if let Some(direction) = file.start_direction {
+        config.start_direction = Vec2::from(direction);
+    }
+    if let Some(scale_start) = file.scale_start {
+        config.scale_start = scale_start;
+    }
+    if let Some(scale_delta) = file.scale_delta {
+        config.scale_delta = scale_delta;
+    }
+    if let Some(scale_min) = file.scale_min {
+        config.scale_min = scale_min;
+    }
+    if let Some(tropism) = file.tropism {
+        config.tropism = Some(Vec2::from(tropism));
+    }
+    if let Some(susceptibility) = file.susceptibility {
+        config.susceptibility = susceptibility;
+    }
+    if let Some(jitter) = file.jitter {
+        config.jitter.angle = jitter.angle_degrees.to_radians();
+        config.jitter.length = jitter.length;
+        config.jitter.distribution = jitter.distribution;
+        config.jitter.seed = jitter.seed;
+    }
+
+    if let Some(line_weight) = file.line_weight {
+        config.line_weight = line_weight;
+    }
+    if let Some(width_mode) = file.width_mode {
+        config.width_mode = width_mode;
+    }
+    if let Some(main_color) = file.main_color {
+        config.main_color = main_color;
+    }
+    if let Some(color_mode) = file.color_mode {
+        config.color_scheme.mode = color_mode;
+    }
+    if let Some(palette) = file.palette {
+        config.color_scheme.palette = palette;
+    }
+    if let Some(wind_power) = file.wind_power {
+        config.wind_power = Some(Vec2::from(wind_power));
+    }
+
+    if let Some(bg_color) = file.bg_color {
+        app_config.bg_color = bg_color;
+    }
+    if let Some(dots_pro_second) = file.dots_pro_second {
+        app_config.dots_pro_second = dots_pro_second;
+    }
+    if let Some(scheduling) = file.scheduling {
+        app_config.scheduling = scheduling;
+    }
+    if let Some(color_schemes) = file.color_schemes {
+        app_config.color_schemes = color_schemes;
+    }
+    if let Some(start_point) = file.start_point {
+        app_config.start_point = Some(Vec2::from(start_point));
+    }
+    if let Some(start_point_delta) = file.start_point_delta {
+        app_config.start_point_delta = Some(Vec2::from(start_point_delta));
+    }
+    if let Some(mesh_segments) = file.mesh_segments {
+        app_config.mesh_segments = mesh_segments;
+    }
+    if let Some(planted) = file.planted {
+        app_config.planted = planted
+            .into_iter()
+            .map(|planted| Planted {
+                point: Vec2::from(planted.point),
+                preset: planted.preset,
+                deep: planted.deep,
+                start: planted.start,
+            })
+            .collect();
+    }
+    if let Some(preset) = file.plant_preset {
+        app_config.plant_preset = PRESETS
+            .iter()
+            .position(|(name, _, _)| *name == preset)
+            .ok_or_else(|| format!("There is no preset named {}", preset))?;
+    }
+    if let Some(plant_deep) = file.plant_deep {
+        app_config.plant_deep = plant_deep;
+    }
+    Ok(())
+}
+
+// the symbols are strings in the file
+fn one_char(symbol: &str) -> Result<char, String> {
+    let mut chars = symbol.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(ch),
+        _ => Err(format!("The symbol {:?} is not one char", symbol)),
+    }
+}
+
+// writes the app config as a json config file, that can be loaded again
+pub fn save(path: &Path, app_config: &AppConfig) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(&to_file(app_config)).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+// every setting of the app config, so the file doesn't depend on the constants
+fn to_file(app_config: &AppConfig) -> ConfigFile {
+    let config = &app_config.config;
+    let mut rules = config
+        .rules
+        .iter()
+        .map(|(ch, text, behaviour)| RuleFile {
+            symbol: ch.to_string(),
+            to: text.clone(),
+            behaviour: behaviour.clone(),
+        })
+        .collect::<Vec<RuleFile>>();
+    // the rules are in a hash map, so they are sorted for the stable files
+    rules.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+    ConfigFile {
+        deeps: Some(app_config.deeps.clone()),
+        axiom: Some(config.axiom.clone()),
+        rules,
+        shapes: config
+            .shapes
+            .iter()
+            .map(|shape| match &shape.source {
+                ShapeSource::SvgPath(path) => ShapeFile {
+                    name: shape.name.clone(),
+                    path: Some(path.clone()),
+                    points: None,
+                },
+                ShapeSource::Points(points) => ShapeFile {
+                    name: shape.name.clone(),
+                    path: None,
+                    points: Some(points.clone()),
+                },
+            })
+            .collect(),
+        rotation_degrees: Some(config.rotation_factor.to_degrees()),
+        angle_divisor: None,
+        symbol_angles: config
+            .symbol_angles
+            .iter()
+            .map(|(ch, angle)| (ch.to_string(), angle.to_degrees()))
+            .collect(),
+        symbol_steps: config
+            .symbol_steps
+            .iter()
+            .map(|(ch, step)| (ch.to_string(), *step))
+            .collect(),
+        start_direction: Some(config.start_direction.into()),
+        scale_start: Some(config.scale_start),
+        scale_delta: Some(config.scale_delta),
+        scale_min: Some(config.scale_min),
+        tropism: config.tropism.map(|tropism| tropism.into()),
+        susceptibility: Some(config.susceptibility),
+        jitter: Some(JitterFile {
+            angle_degrees: config.jitter.angle.to_degrees(),
+            length: config.jitter.length,
+            distribution: config.jitter.distribution,
+            seed: config.jitter.seed,
+        }),
+        line_weight: Some(config.line_weight),
+        width_mode: Some(config.width_mode),
+        bg_color: Some(app_config.bg_color.clone()),
+        main_color: Some(config.main_color.clone()),
+        color_mode: Some(config.color_scheme.mode),
+        palette: Some(config.color_scheme.palette.clone()),
+        dots_pro_second: Some(app_config.dots_pro_second),
+        scheduling: Some(app_config.scheduling),
+        wind_power: config.wind_power.map(|wind_power| wind_power.into()),
+        color_schemes: Some(app_config.color_schemes.clone()),
+        start_point: app_config.start_point.map(|point| point.into()),
+        start_point_delta: app_config.start_point_delta.map(|delta| delta.into()),
+        mesh_segments: Some(app_config.mesh_segments),
+        planted: Some(
+            app_config
+                .planted
+                .iter()
+                .map(|planted| PlantedFile {
+                    point: planted.point.into(),
+                    preset: planted.preset.clone(),
+                    deep: planted.deep,
+                    start: planted.start,
+                })
+                .collect(),
+        ),
+        plant_preset: Some(PRESETS[app_config.plant_preset].0.to_string()),
+        plant_deep: Some(app_config.plant_deep),
+    }
+}
+
+// finds the mistakes, on which the builder would panic
+fn validate(app_config: &AppConfig) -> Result<(), String> {
+    let config = &app_config.config;
+    if app_config.deeps.is_empty() {
+        return Err("There are no deeps".to_string());
+    }
+
+    let colors = [&app_config.bg_color, &config.main_color]
+        .into_iter()
+        .chain(config.color_scheme.palette.iter())
+        .chain(
+            app_config
+                .color_schemes
+                .iter()
+                .flat_map(|color_scheme| color_scheme.palette.iter()),
+        );
+    for hex in colors {
+        if !is_hex_color(hex) {
+            return Err(format!("The color {} is not \"#RRGGBB\"", hex));
+        }
+    }
+
+    let texts = std::iter::once(&config.axiom).chain(config.rules.iter().map(|(_, text, _)| text));
+    for text in texts {
+        if let Some(ch) = text
+            .chars()
+            .find(|ch| config.rules.get_behaviour(ch).is_none())
+        {
+            return Err(format!("No rule for {} in {}", ch, text));
+        }
+    }
+
+    if config.axiom.is_empty() {
+        return Err("The axiom is empty".to_string());
+    }
+    for (open, close, name) in [
+        (Behaviour::Branch, Behaviour::BranchStop, "[ ]"),
+        (Behaviour::StartPolygon, Behaviour::EndPolygon, "{ }"),
+    ] {
+        let net = |ch: &char| match config.rules.get_behaviour(ch) {
+            Some(behaviour) if *behaviour == open => 1,
+            Some(behaviour) if *behaviour == close => -1,
+            _ => 0,
+        };
+        // the axiom is balanced and every rule keeps the balance of its symbol, so every
+        // sequence is balanced
+        if !is_balanced(&config.axiom, 0, net) {
+            return Err(format!("The {} in the axiom are not balanced", name));
+        }
+        for (ch, text, _) in config.rules.iter() {
+            if !is_balanced(text, net(ch), net) {
+                return Err(format!(
+                    "The {} in the rule of {} are not balanced",
+                    name, ch
+                ));
+            }
+        }
+    }
+    if symbols_before_first_dot(config)
+        .iter()
+        .any(|ch| config.rules.get_behaviour(ch) == Some(&Behaviour::Branch))
+    {
+        return Err("There can be a branch before the first drawn dot".to_string());
+    }
+    if let Some(ch) = symbols_outside_polygons(config)
+        .iter()
+        .find(|ch| config.rules.get_behaviour(ch) == Some(&Behaviour::PolygonVertex))
+    {
+        return Err(format!("The {} can be outside of {{ }}", ch));
+    }
+
+    if app_config.mesh_segments < 3 {
+        return Err("The meshes need at least 3 segments".to_string());
+    }
+    if config.jitter.angle < 0.0 || config.jitter.length < 0.0 {
+        return Err("The jitter is negative".to_string());
+    }
+    if app_config.dots_pro_second.is_nan() || app_config.dots_pro_second <= 0.0 {
+        return Err("The dots pro second are not positive".to_string());
+    }
+
+    if let Some(shape) = config
+        .shapes
+        .iter()
+        .find(|shape| shape.try_outline().is_none())
+    {
+        return Err(format!("The svg path of the shape {} is wrong", shape.name));
+    }
+    for (ch, _, behaviour) in config.rules.iter() {
+        if let Behaviour::StampShape(name) = behaviour {
+            if !config.shapes.iter().any(|shape| shape.name == *name) {
+                return Err(format!("There is no shape named {} for {}", name, ch));
+            }
+        }
+    }
+
+    if let Some(deep) = app_config.deeps.iter().find(|deep| !config.fits(**deep)) {
+        return Err(format!(
+            "The tree of deep {} has more than {} symbols",
+            deep, MAX_SYMBOLS
+        ));
+    }
+
+    for planted in app_config.planted.iter() {
+        match app_config.planted_config(planted) {
+            Some(planted_config) if !planted_config.fits(planted.deep) => {
+                return Err(format!(
+                    "The planted {} of deep {} has more than {} symbols",
+                    planted.preset, planted.deep, MAX_SYMBOLS
+                ))
+            }
+            Some(_) => {}
+            None => return Err(format!("There is no preset named {}", planted.preset)),
+        }
+    }
+    Ok(())
+}
+
+// true, if the text changes the depth by its own net and never goes deeper below the start, than
+// its own net goes
+fn is_balanced(text: &str, own_net: i32, net: impl Fn(&char) -> i32) -> bool {
+    let mut depth = 0;
+    for ch in text.chars() {
+        depth += net(&ch);
+        if depth < own_net.min(0) {
+            return false;
+        }
+    }
+    depth == own_net
+}
+
+// the symbols, that can come before the first drawn dot in the sequence of any deep, the
+// branches need a drawn dot to fork from
+fn symbols_before_first_dot(config: &LsystemConfig) -> Vec<char> {
+    let drawless = drawless_symbols(config);
+    let mut found = vec![];
+    let mut texts = vec![config.axiom.as_str()];
+    while let Some(text) = texts.pop() {
+        for ch in text.chars() {
+            if !found.contains(&ch) {
+                found.push(ch);
+                texts.extend(config.rules.get_text(&ch).map(String::as_str));
+            }
+            if !drawless.contains(&ch) {
+                break;
+            }
+        }
+    }
+    found
+}
+
+// the symbols, that can be the sequence without a drawn dot in some deep: the ones, that don't
+// draw, and the ones with a rule of such symbols
+fn drawless_symbols(config: &LsystemConfig) -> Vec<char> {
+    let mut drawless = config
+        .rules
+        .iter()
+        .filter(|(_, _, behaviour)| **behaviour != Behaviour::DrawForward)
+        .map(|(ch, _, _)| *ch)
+        .collect::<Vec<char>>();
+    loop {
+        let more = config
+            .rules
+            .iter()
+            .filter(|(ch, text, _)| {
+                !drawless.contains(ch) && text.chars().all(|ch| drawless.contains(&ch))
+            })
+            .map(|(ch, _, _)| *ch)
+            .collect::<Vec<char>>();
+        if more.is_empty() {
+            return drawless;
+        }
+        drawless.extend(more);
+    }
+}
+
+// the symbols, that can be outside of every polygon in the sequence of any deep. The rules keep
+// the balance, so the symbols of a rule stay at most one polygon above the depth of their symbol
+// and only the symbols in the depths 0 and 1 can have the symbols in the depth 0 after them
+fn symbols_outside_polygons(config: &LsystemConfig) -> Vec<char> {
+    // the symbols with the depth of the polygons before them, the deeper ones are in the depth 2
+    let mut found = vec![];
+    let mut texts = vec![(config.axiom.as_str(), 0)];
+    while let Some((text, start)) = texts.pop() {
+        let mut depth = start;
+        for ch in text.chars() {
+            let symbol = (ch, depth.min(2));
+            if !found.contains(&symbol) {
+                found.push(symbol);
+                if depth <= 1 {
+                    texts.extend(
+                        config
+                            .rules
+                            .get_text(&ch)
+                            .map(|text| (text.as_str(), depth)),
+                    );
+                }
+            }
+            match config.rules.get_behaviour(&ch) {
+                Some(Behaviour::StartPolygon) => depth += 1,
+                Some(Behaviour::EndPolygon) => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    found
+        .into_iter()
+        .filter(|(_, depth)| *depth == 0)
+        .map(|(ch, _)| ch)
+        .collect()
+}
+
+// checks the modification time of the config file, so the viewer can load it again after every
+// save
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    since_check: Duration,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> ConfigWatcher {
+        ConfigWatcher {
+            modified: modified(&path),
+            path,
+            since_check: Duration::ZERO,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // returns true, if the file was changed since the last time
+    pub fn changed(&mut self, since_last: Duration) -> bool {
+        self.since_check += since_last;
+        if self.since_check < CHECK_INTERVAL {
+            return false;
+        }
+        self.since_check = Duration::ZERO;
+
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+// None, while the file doesn't exist, for example while the editor writes it
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate_with(change: impl Fn(&mut AppConfig)) -> Result<(), String> {
+        let mut app_config = AppConfig::new(vec![3]);
+        change(&mut app_config);
+        validate(&app_config)
+    }
+
+    fn add_rule(app_config: &mut AppConfig, ch: char, text: &str) {
+        app_config
+            .config
+            .rules
+            .insert(Rule::new(ch, text, Behaviour::DrawForward));
+    }
+
+    #[test]
+    fn constants_are_valid() {
+        assert!(validate_with(|_| {}).is_ok());
+        assert!(
+            validate_with(|app_config| app_config.config.axiom = "F{.F.F.}".to_string()).is_ok()
+        );
+    }
+
+    #[test]
+    fn unbalanced_branches_are_refused() {
+        for axiom in ["F[F", "F]F", "F][F", ""] {
+            let axiom = axiom.to_string();
+            assert!(validate_with(|app_config| app_config.config.axiom = axiom.clone()).is_err());
+        }
+        // the rule can't take the branch of its symbol
+        assert!(validate_with(|app_config| add_rule(app_config, 'X', "F]X[")).is_err());
+        assert!(validate_with(|app_config| add_rule(app_config, 'X', "F[X")).is_err());
+    }
+
+    #[test]
+    fn branches_need_a_drawn_dot_before_them() {
+        assert!(validate_with(|app_config| app_config.config.axiom = "[F]F".to_string()).is_err());
+        assert!(
+            validate_with(|app_config| app_config.config.axiom = "+f[F]F".to_string()).is_err()
+        );
+        assert!(validate_with(|app_config| app_config.config.axiom = "Ff[F]".to_string()).is_ok());
+        assert!(validate_with(|app_config| add_rule(app_config, 'X', "[F]X")).is_err());
+        // the symbol with the empty rule vanishes
+        assert!(validate_with(|app_config| {
+            app_config.config.axiom = "AX".to_string();
+            add_rule(app_config, 'A', "");
+            add_rule(app_config, 'X', "[F]X");
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn vertices_outside_of_polygons_are_refused() {
+        for axiom in ["F.F", "F{.}.", "F}F{", "F{F"] {
+            let axiom = axiom.to_string();
+            assert!(validate_with(|app_config| app_config.config.axiom = axiom.clone()).is_err());
+        }
+        // the vertex of the rule is outside, when its symbol is outside
+        assert!(validate_with(|app_config| {
+            app_config.config.axiom = "FA{A}".to_string();
+            add_rule(app_config, 'A', ".");
+        })
+        .is_err());
+        assert!(validate_with(|app_config| {
+            app_config.config.axiom = "F{A}".to_string();
+            add_rule(app_config, 'A', "F.A");
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn unknown_symbols_are_refused() {
+        assert!(validate_with(|app_config| app_config.config.axiom = "FQ".to_string()).is_err());
+        assert!(validate_with(|app_config| add_rule(app_config, 'X', "FQX")).is_err());
+    }
+
+    #[test]
+    fn negative_settings_are_refused() {
+        assert!(validate_with(|app_config| app_config.config.jitter.angle = -0.1).is_err());
+        assert!(validate_with(|app_config| app_config.config.jitter.length = -0.1).is_err());
+        assert!(validate_with(|app_config| app_config.dots_pro_second = 0.0).is_err());
+        assert!(validate_with(|app_config| app_config.dots_pro_second = f32::NAN).is_err());
+    }
+
+    #[test]
+    fn saved_config_is_loaded_the_same() {
+        let mut app_config = AppConfig::new(vec![2, 3]);
+        app_config.config.axiom = "FX".to_string();
+        app_config.config.tropism = Some(Vec2::new(0.0, -1.0));
+        app_config.config.jitter.length = 0.25;
+        app_config.color_schemes =
+            vec![ColorScheme::new(ColorMode::Depth, &["#112233", "#445566"])];
+        app_config.start_point = Some(Vec2::new(-50.0, -100.0));
+        app_config.mesh_segments = 5;
+        app_config.plant_preset = 2;
+        app_config.plant_deep = 3;
+        app_config.planted.push(Planted {
+            point: Vec2::new(10.0, 20.0),
+            preset: "bush".to_string(),
+            deep: 2,
+            start: 40.0,
+        });
+
+        let path = std::env::temp_dir().join(format!("trees_{}.json", std::process::id()));
+        save(&path, &app_config).unwrap();
+        let loaded = load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        // the settings, that are not the constants, come back
+        assert_eq!(loaded.deeps, vec![2, 3]);
+        assert_eq!(loaded.config.axiom, "FX");
+        assert!(loaded.color_schemes[0].mode == ColorMode::Depth);
+        assert_eq!(loaded.start_point, Some(Vec2::new(-50.0, -100.0)));
+        assert_eq!(loaded.mesh_segments, 5);
+        assert_eq!((loaded.plant_preset, loaded.plant_deep), (2, 3));
+        assert_eq!(loaded.planted[0].preset, "bush");
+        let saved = serde_json::to_value(to_file(&app_config)).unwrap();
+        let loaded = serde_json::to_value(to_file(&loaded)).unwrap();
+        assert_eq!(saved, loaded);
+    }
+
+    #[test]
+    fn reloaded_config_keeps_the_planted_trees() {
+        let mut current = AppConfig::new(vec![3]);
+        current.plant_preset = 1;
+        current.plant_deep = 2;
+        current.planted.push(Planted {
+            point: Vec2::new(10.0, 20.0),
+            preset: "bush".to_string(),
+            deep: 2,
+            start: 40.0,
+        });
+
+        let path = std::env::temp_dir().join(format!("trees_reload_{}.json", std::process::id()));
+        fs::write(&path, r#"{ "axiom": "FX" }"#).unwrap();
+        let reloaded = reload(&path, &current);
+        let loaded = load(&path);
+        // the saved scene replaces the planted trees
+        save(&path, &AppConfig::new(vec![3])).unwrap();
+        let replaced = reload(&path, &current);
+        fs::remove_file(&path).unwrap();
+
+        let reloaded = reloaded.unwrap();
+        assert_eq!(reloaded.config.axiom, "FX");
+        assert_eq!(reloaded.planted.len(), 1);
+        assert_eq!((reloaded.plant_preset, reloaded.plant_deep), (1, 2));
+        assert!(loaded.unwrap().planted.is_empty());
+        let replaced = replaced.unwrap();
+        assert!(replaced.planted.is_empty());
+        assert_eq!(replaced.plant_deep, crate::constants::PLANT_DEEP);
+    }
+
+    #[test]
+    fn too_big_trees_are_refused() {
+        assert!(validate_with(|app_config| app_config.deeps = vec![3, 100]).is_err());
+    }
+}
@@ -1,19 +1,84 @@
+use crate::{
+    animation::Scheduling,
+    lsystem::{ColorMode, Distribution, WidthMode},
+};
+
 // options for rendering
 pub const BG_COLOR: &str = "#F2F7F2";
 pub const FG_COLOR: &str = "#FF9FB2";
+// how the branches are colored, the palette is used by the SetColor symbols and the gradients
+pub const COLOR_MODE: ColorMode = ColorMode::Single;
+pub const PALETTE: &[&str] = &["#5C4033", "#7BA05B", "#FF9FB2"];
+// the color scheme of every tree, if there are less schemes than trees, the rest uses the one
+// above
+pub const TREE_COLOR_SCHEMES: &[(ColorMode, &[&str])] = &[];
 pub const START_POINT: Option<(f32, f32)> = None;
 pub const START_POINT_DELTA: Option<(f32, f32)> = None;
+// the grammars, that are planted by the click in the window: the name, the axiom and the rules,
+// the symbols without a rule in the config draw forward
+pub type Preset = (&'static str, &'static str, &'static [(char, &'static str)]);
+pub const PRESETS: &[Preset] = &[
+    ("plant", "X", &[('X', "F+[[X]-X]-F[-FX]+X"), ('F', "FF")]),
+    ("bush", "F", &[('F', "FF+[+F-F-F]-[-F+F+F]")]),
+    ("weed", "X", &[('X', "F[+X]F[-X]+X"), ('F', "FF")]),
+    ("leafy", "X", &[('X', "F[+XL][-XL]FX"), ('F', "FF")]),
+];
+// the depth of the planted trees, until it is changed in the panel
+pub const PLANT_DEEP: usize = 5;
 
 // for animation
-pub const DOTS_PRO_SECOND: usize = 1;
+pub const DOTS_PRO_SECOND: f32 = 60.0;
 pub const WIND_POWER: Option<(f32, f32)> = None;
 pub const ANIMATE: bool = true;
+// how many branches of all trees can grow at the same time
+pub const MAX_ANIMATED_BRANCHES: usize = 5000;
+// in which order the branches start to grow
+pub const SCHEDULING: Scheduling = Scheduling::Queue;
+// the frame rate of the exported animation
+pub const EXPORT_FPS: f32 = 30.0;
+// the exported animations with more frames are refused, that is 10 minutes with 30 frames
+pub const MAX_EXPORT_FRAMES: usize = 18000;
+// how many sides the branches and the leaves of the exported 3D meshes have
+pub const MESH_SEGMENTS: usize = 8;
 
 // generating options
+pub const DEEPS: &[usize] = &[8];
+// the trees with more symbols after the rules are refused, they would take too long to build
+pub const MAX_SYMBOLS: usize = 10_000_000;
 pub const START_DIRECTION: (f32, f32) = (0.0, 1.0);
 pub const ROTATION_DEGREES: f32 = 30.0;
+// if given, the rotation is the full turn divided by it instead of the degrees above
+pub const ANGLE_DIVISOR: Option<u32> = None;
+// the symbols with their own rotation in degrees and their own step length, the other ones use
+// the rotation and the length of the start direction
+pub const SYMBOL_ANGLES: &[(char, f32)] = &[('>', 60.0), ('<', 60.0)];
+pub const SYMBOL_STEPS: &[(char, f32)] = &[];
 pub const LINE_WEIGHT: f32 = 1.0;
+// how the branches get thicker from the twigs to the trunk
+pub const WIDTH_MODE: WidthMode = WidthMode::Constant;
 pub const AXIOM: &str = "X";
+// the branches bend to this vector on every step, (0.0, -1.0) for gravity
+pub const TROPISM: Option<(f32, f32)> = None;
+pub const SUSCEPTIBILITY: f32 = 0.2;
+// the random deviations of every turn (in degrees) and every step (as the part of its length),
+// every tree of the forest gets the seed plus its index
+pub const ANGLE_JITTER_DEGREES: f32 = 0.0;
+pub const LENGTH_JITTER: f32 = 0.0;
+pub const JITTER_DISTRIBUTION: Distribution = Distribution::Uniform;
+pub const JITTER_SEED: u64 = 0;
+// the shapes for the StampShape symbols as svg path data, in the units of the step with the
+// turtle on the origin heading to +y
+pub const SHAPES: &[(&str, &str)] = &[
+    (
+        "leaf",
+        "M 0 0 C 0.4 0.3 0.3 0.8 0 1 C -0.3 0.8 -0.4 0.3 0 0 Z",
+    ),
+    ("bud", "M 0 0 Q 0.3 0.3 0 0.6 Q -0.3 0.3 0 0 Z"),
+    (
+        "flower",
+        "M 0 0 Q 0.3 0.2 0.2 0.5 Q 0 0.6 -0.2 0.5 Q -0.3 0.2 0 0 Z",
+    ),
+];
 
 // for scaling in progression
 pub const SCALE_START: f32 = 1.0;
@@ -0,0 +1,518 @@
+use std::{collections::BTreeSet, fmt::Display, fs, io, path::Path};
+
+use nannou::{color::Rgb, geom::Point2};
+
+use super::{bounds, color_bytes, rgb_bytes};
+use crate::lsystem::{LsystemConfig, LsystemTree};
+
+// writes the trees as an ascii dxf (AutoCAD 2004, the first version with the true colors), every
+// branch is a tapered LWPOLYLINE on the layer of its branch order. The file has every table,
+// block and object, that the strict readers need, with the handles of all of them
+pub fn write(path: &Path, trees: &[LsystemTree], config: &LsystemConfig) -> io::Result<()> {
+    fs::write(path, to_dxf(trees, config))
+}
+
+// the handles must be unique for every object in the file, the header gets the next free one
+struct Handles(u32);
+
+impl Handles {
+    fn next(&mut self) -> String {
+        self.0 += 1;
+        format!("{:X}", self.0)
+    }
+}
+
+fn to_dxf(trees: &[LsystemTree], config: &LsystemConfig) -> String {
+    // everything after the header, that needs the last handle
+    let mut out = String::new();
+    let mut handles = Handles(0);
+
+    // the layers have the main color, the polylines their own
+    let main_color = true_color(rgb_bytes(&config.main_color));
+
+    let orders = trees
+        .iter()
+        .flat_map(|tree| tree.branches_order.values().cloned())
+        .collect::<BTreeSet<usize>>();
+    let (min, max) = bounds(trees);
+
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "CLASSES");
+    group(&mut out, 0, "ENDSEC");
+
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "TABLES");
+
+    // the active viewport shows all trees
+    let table = table_start(&mut out, &mut handles, "VPORT", 1);
+    table_record(&mut out, &mut handles, "VPORT", &table, "*Active");
+    let center = (min + max) / 2.0;
+    let size = (max - min).max(Point2::splat(1.0));
+    for (code, value) in [
+        (10, 0.0),
+        (20, 0.0),
+        (11, 1.0),
+        (21, 1.0),
+        (12, center.x),
+        (22, center.y),
+        (13, 0.0),
+        (23, 0.0),
+        (14, 1.0),
+        (24, 1.0),
+        (15, 0.0),
+        (25, 0.0),
+        (16, 0.0),
+        (26, 0.0),
+        (36, 1.0),
+        (17, 0.0),
+        (27, 0.0),
+        (37, 0.0),
+        (40, size.y * 1.1),
+        (41, size.x / size.y),
+        (42, 50.0),
+        (43, 0.0),
+        (44, 0.0),
+        (50, 0.0),
+        (51, 0.0),
+    ] {
+        group(&mut out, code, value);
+    }
+    for (code, value) in [(71, 0), (72, 100), (73, 1), (74, 3), (75, 0), (76, 0)] {
+        group(&mut out, code, value);
+    }
+    group(&mut out, 0, "ENDTAB");
+
+    let table = table_start(&mut out, &mut handles, "LTYPE", 3);
+    for (name, description) in [
+        ("ByBlock", ""),
+        ("ByLayer", ""),
+        ("Continuous", "Solid line"),
+    ] {
+        table_record(&mut out, &mut handles, "LTYPE", &table, name);
+        group(&mut out, 3, description);
+        group(&mut out, 72, 65);
+        group(&mut out, 73, 0);
+        group(&mut out, 40, 0.0);
+    }
+    group(&mut out, 0, "ENDTAB");
+
+    // the layer 0 is in every dxf file
+    let table = table_start(&mut out, &mut handles, "LAYER", orders.len() + 1);
+    table_record(&mut out, &mut handles, "LAYER", &table, "0");
+    group(&mut out, 62, 7);
+    group(&mut out, 6, "Continuous");
+    for order in orders.iter() {
+        table_record(&mut out, &mut handles, "LAYER", &table, &layer_name(*order));
+        group(&mut out, 62, 7);
+        group(&mut out, 420, main_color);
+        group(&mut out, 6, "Continuous");
+    }
+    group(&mut out, 0, "ENDTAB");
+
+    let table = table_start(&mut out, &mut handles, "STYLE", 1);
+    table_record(&mut out, &mut handles, "STYLE", &table, "Standard");
+    group(&mut out, 40, 0.0);
+    group(&mut out, 41, 1.0);
+    group(&mut out, 50, 0.0);
+    group(&mut out, 71, 0);
+    group(&mut out, 42, 2.5);
+    group(&mut out, 3, "txt");
+    group(&mut out, 4, "");
+    group(&mut out, 0, "ENDTAB");
+
+    table_start(&mut out, &mut handles, "VIEW", 0);
+    group(&mut out, 0, "ENDTAB");
+    table_start(&mut out, &mut handles, "UCS", 0);
+    group(&mut out, 0, "ENDTAB");
+
+    let table = table_start(&mut out, &mut handles, "APPID", 1);
+    table_record(&mut out, &mut handles, "APPID", &table, "ACAD");
+    group(&mut out, 0, "ENDTAB");
+
+    let table = table_start(&mut out, &mut handles, "DIMSTYLE", 1);
+    table_record(&mut out, &mut handles, "DIMSTYLE", &table, "Standard");
+    group(&mut out, 0, "ENDTAB");
+
+    // the entities are owned by the record of the model space
+    let table = table_start(&mut out, &mut handles, "BLOCK_RECORD", 2);
+    let model_space = table_record(
+        &mut out,
+        &mut handles,
+        "BLOCK_RECORD",
+        &table,
+        "*Model_Space",
+    );
+    let paper_space = table_record(
+        &mut out,
+        &mut handles,
+        "BLOCK_RECORD",
+        &table,
+        "*Paper_Space",
+    );
+    group(&mut out, 0, "ENDTAB");
+    group(&mut out, 0, "ENDSEC");
+
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "BLOCKS");
+    for (name, record) in [
+        ("*Model_Space", &model_space),
+        ("*Paper_Space", &paper_space),
+    ] {
+        group(&mut out, 0, "BLOCK");
+        group(&mut out, 5, handles.next());
+        group(&mut out, 330, record);
+        group(&mut out, 100, "AcDbEntity");
+        if record == &paper_space {
+            group(&mut out, 67, 1);
+        }
+        group(&mut out, 8, "0");
+        group(&mut out, 100, "AcDbBlockBegin");
+        group(&mut out, 2, name);
+        group(&mut out, 70, 0);
+        group(&mut out, 10, 0.0);
+        group(&mut out, 20, 0.0);
+        group(&mut out, 30, 0.0);
+        group(&mut out, 3, name);
+        group(&mut out, 1, "");
+        group(&mut out, 0, "ENDBLK");
+        group(&mut out, 5, handles.next());
+        group(&mut out, 330, record);
+        group(&mut out, 100, "AcDbEntity");
+        if record == &paper_space {
+            group(&mut out, 67, 1);
+        }
+        group(&mut out, 8, "0");
+        group(&mut out, 100, "AcDbBlockEnd");
+    }
+    group(&mut out, 0, "ENDSEC");
+
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "ENTITIES");
+    for tree in trees {
+        for id in tree.branch_ids() {
+            // a polyline has only one color, so every part of the branch with its own color is
+            // a polyline
+            for (stroke, color) in tree.branch_stroke(&id).color_runs() {
+                entity(
+                    &mut out,
+                    &mut handles,
+                    "LWPOLYLINE",
+                    &model_space,
+                    &layer_name(tree.branch_order(&id)),
+                    color,
+                );
+                group(&mut out, 100, "AcDbPolyline");
+                group(&mut out, 90, stroke.points.len());
+                group(&mut out, 70, 0);
+                for (i, point) in stroke.points.iter().enumerate() {
+                    // the segment, that starts on the vertex, tapers from its width to the next
+                    // one
+                    let next = (i + 1).min(stroke.widths.len() - 1);
+                    group(&mut out, 10, point.x);
+                    group(&mut out, 20, point.y);
+                    group(&mut out, 40, stroke.widths[i]);
+                    group(&mut out, 41, stroke.widths[next]);
+                }
+            }
+        }
+
+        // a polygon and a shape are solid hatches with their outline as the boundary
+        for polygon in tree.polygons.iter() {
+            let layer = layer_name(tree.branch_order(&polygon.branch_id));
+            entity(
+                &mut out,
+                &mut handles,
+                "HATCH",
+                &model_space,
+                &layer,
+                polygon.color,
+            );
+            hatch(&mut out, &polygon.points);
+        }
+        for instance in tree.shape_instances.iter() {
+            let layer = layer_name(tree.branch_order(&instance.branch_id));
+            entity(
+                &mut out,
+                &mut handles,
+                "HATCH",
+                &model_space,
+                &layer,
+                instance.color,
+            );
+            hatch(&mut out, &tree.shape_outline(instance));
+        }
+
+        // a filled circle is a closed polyline of two half circles with the width of the
+        // radius, on the layer of its branch
+        for circle in tree.circles.iter() {
+            entity(
+                &mut out,
+                &mut handles,
+                "LWPOLYLINE",
+                &model_space,
+                &layer_name(tree.branch_order(&circle.branch_id)),
+                circle.color,
+            );
+            group(&mut out, 100, "AcDbPolyline");
+            group(&mut out, 90, 2);
+            group(&mut out, 70, 1);
+            group(&mut out, 43, circle.radius);
+            for side in [-1.0, 1.0] {
+                group(&mut out, 10, circle.pos.x + side * circle.radius / 2.0);
+                group(&mut out, 20, circle.pos.y);
+                group(&mut out, 42, 1.0);
+            }
+        }
+    }
+    group(&mut out, 0, "ENDSEC");
+
+    // the root dictionary with the dictionary of the groups
+    let root = handles.next();
+    let groups = handles.next();
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "OBJECTS");
+    group(&mut out, 0, "DICTIONARY");
+    group(&mut out, 5, &root);
+    group(&mut out, 330, 0);
+    group(&mut out, 100, "AcDbDictionary");
+    group(&mut out, 281, 1);
+    group(&mut out, 3, "ACAD_GROUP");
+    group(&mut out, 350, &groups);
+    group(&mut out, 0, "DICTIONARY");
+    group(&mut out, 5, &groups);
+    group(&mut out, 330, &root);
+    group(&mut out, 100, "AcDbDictionary");
+    group(&mut out, 281, 1);
+    group(&mut out, 0, "ENDSEC");
+    group(&mut out, 0, "EOF");
+
+    let mut header = String::new();
+    group(&mut header, 0, "SECTION");
+    group(&mut header, 2, "HEADER");
+    group(&mut header, 9, "$ACADVER");
+    group(&mut header, 1, "AC1018");
+    group(&mut header, 9, "$DWGCODEPAGE");
+    group(&mut header, 3, "ANSI_1252");
+    group(&mut header, 9, "$HANDSEED");
+    group(&mut header, 5, handles.next());
+    group(&mut header, 9, "$EXTMIN");
+    group(&mut header, 10, min.x);
+    group(&mut header, 20, min.y);
+    group(&mut header, 30, 0.0);
+    group(&mut header, 9, "$EXTMAX");
+    group(&mut header, 10, max.x);
+    group(&mut header, 20, max.y);
+    group(&mut header, 30, 0.0);
+    group(&mut header, 0, "ENDSEC");
+
+    header + &out
+}
+
+// the head of the table with the count of its records, gives the handle of the table
+fn table_start(out: &mut String, handles: &mut Handles, name: &str, count: usize) -> String {
+    let handle = handles.next();
+    group(out, 0, "TABLE");
+    group(out, 2, name);
+    group(out, 5, &handle);
+    group(out, 330, 0);
+    group(out, 100, "AcDbSymbolTable");
+    group(out, 70, count);
+    if name == "DIMSTYLE" {
+        group(out, 100, "AcDbDimStyleTable");
+        group(out, 71, 0);
+    }
+    handle
+}
+
+// the common part of a record of the table, gives the handle of the record
+fn table_record(
+    out: &mut String,
+    handles: &mut Handles,
+    kind: &str,
+    table: &str,
+    name: &str,
+) -> String {
+    let handle = handles.next();
+    group(out, 0, kind);
+    // only the dimension styles have their handle in the other group code
+    group(out, if kind == "DIMSTYLE" { 105 } else { 5 }, &handle);
+    group(out, 330, table);
+    group(out, 100, "AcDbSymbolTableRecord");
+    let subclass = match kind {
+        "VPORT" => "AcDbViewportTableRecord",
+        "LTYPE" => "AcDbLinetypeTableRecord",
+        "LAYER" => "AcDbLayerTableRecord",
+        "STYLE" => "AcDbTextStyleTableRecord",
+        "APPID" => "AcDbRegAppTableRecord",
+        "DIMSTYLE" => "AcDbDimStyleTableRecord",
+        _ => "AcDbBlockTableRecord",
+    };
+    group(out, 100, subclass);
+    group(out, 2, name);
+    // the block records have no flags
+    if kind != "BLOCK_RECORD" {
+        group(out, 70, 0);
+    }
+    handle
+}
+
+// the common part of an entity in the model space
+fn entity(
+    out: &mut String,
+    handles: &mut Handles,
+    kind: &str,
+    owner: &str,
+    layer: &str,
+    color: Rgb,
+) {
+    group(out, 0, kind);
+    group(out, 5, handles.next());
+    group(out, 330, owner);
+    group(out, 100, "AcDbEntity");
+    group(out, 8, layer);
+    group(out, 420, true_color(color_bytes(color)));
+}
+
+// the rest of a solid filled HATCH with one closed polyline boundary
+fn hatch(out: &mut String, points: &[Point2]) {
+    group(out, 100, "AcDbHatch");
+    group(out, 10, 0.0);
+    group(out, 20, 0.0);
+    group(out, 30, 0.0);
+    group(out, 210, 0.0);
+    group(out, 220, 0.0);
+    group(out, 230, 1.0);
+    group(out, 2, "SOLID");
+    // solid fill, not associative, one closed polyline boundary without bulges
+    group(out, 70, 1);
+    group(out, 71, 0);
+    group(out, 91, 1);
+    group(out, 92, 2);
+    group(out, 72, 0);
+    group(out, 73, 1);
+    group(out, 93, points.len());
+    for point in points.iter() {
+        group(out, 10, point.x);
+        group(out, 20, point.y);
+    }
+    group(out, 97, 0);
+    group(out, 75, 0);
+    group(out, 76, 1);
+    group(out, 98, 0);
+}
+
+// the 24 bit color of the group code 420
+fn true_color((r, g, b): (u8, u8, u8)) -> u32 {
+    (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+fn layer_name(order: usize) -> String {
+    format!("BRANCH_ORDER_{}", order)
+}
+
+// one group code and value pair of the dxf file
+fn group(out: &mut String, code: u32, value: impl Display) {
+    out.push_str(&format!("{:>3}\n{}\n", code, value));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::export::test_trees;
+
+    // the pairs of the group codes and the values
+    fn groups(dxf: &str) -> Vec<(u32, String)> {
+        let lines = dxf.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len() % 2, 0);
+        lines
+            .chunks(2)
+            .map(|pair| (pair[0].trim().parse().unwrap(), pair[1].to_string()))
+            .collect()
+    }
+
+    fn dxf() -> Vec<(u32, String)> {
+        let (trees, app_config) = test_trees();
+        let trees = trees.into_iter().map(|tree| tree.tree).collect::<Vec<_>>();
+        groups(&to_dxf(&trees, &app_config.config))
+    }
+
+    #[test]
+    fn has_the_sections_in_order() {
+        let sections = dxf()
+            .windows(2)
+            .filter(|pair| pair[0] == (0, "SECTION".to_string()))
+            .map(|pair| pair[1].1.clone())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            sections,
+            ["HEADER", "CLASSES", "TABLES", "BLOCKS", "ENTITIES", "OBJECTS"]
+        );
+        assert_eq!(dxf().last().unwrap(), &(0, "EOF".to_string()));
+    }
+
+    #[test]
+    fn handles_are_unique_and_below_the_seed() {
+        let groups = dxf();
+        let seed_i = groups
+            .iter()
+            .position(|group| group.1 == "$HANDSEED")
+            .unwrap();
+        let seed = u32::from_str_radix(&groups[seed_i + 1].1, 16).unwrap();
+
+        let mut handles = HashSet::new();
+        for (code, value) in groups[seed_i + 2..].iter() {
+            if *code == 5 || *code == 105 {
+                let handle = u32::from_str_radix(value, 16).unwrap();
+                assert!(handles.insert(handle), "the handle {} is twice", value);
+                assert!(handle < seed);
+            }
+        }
+        // every owner is an object of the file
+        for (code, value) in groups.iter() {
+            if *code == 330 && value != "0" {
+                assert!(handles.contains(&u32::from_str_radix(value, 16).unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn entities_use_defined_layers_and_linetypes() {
+        let groups = dxf();
+        // the names of the records of the table
+        let records = |kind: &str| {
+            let mut names = HashSet::new();
+            let mut in_record = false;
+            for (code, value) in groups.iter() {
+                if *code == 0 {
+                    in_record = value == kind;
+                } else if in_record && *code == 2 {
+                    names.insert(value.to_lowercase());
+                }
+            }
+            names
+        };
+        let layers = records("LAYER");
+        let linetypes = records("LTYPE");
+
+        let entities_i = groups
+            .iter()
+            .position(|group| group.1 == "ENTITIES")
+            .unwrap();
+        let mut polylines = 0;
+        for (code, value) in groups[entities_i..].iter() {
+            match code {
+                8 => assert!(layers.contains(&value.to_lowercase())),
+                0 if value == "LWPOLYLINE" => polylines += 1,
+                _ => {}
+            }
+        }
+        assert!(polylines > 0);
+        for (code, value) in groups.iter() {
+            if *code == 6 {
+                assert!(linetypes.contains(&value.to_lowercase()));
+            }
+        }
+    }
+}
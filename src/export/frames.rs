@@ -0,0 +1,242 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use nannou::image::{codecs::gif::GifEncoder, Delay, Frame, ImageError, RgbaImage};
+
+use super::{bounds, raster::Canvas, rgb_bytes};
+use crate::{
+    animation::TreeAnimation,
+    config::AppConfig,
+    constants::{EXPORT_FPS, MAX_ANIMATED_BRANCHES, MAX_EXPORT_FRAMES},
+    lsystem::LsystemTree,
+};
+
+// writes the growth animation as an animated gif
+pub fn write_gif(path: &Path, trees: &[LsystemTree], app_config: &AppConfig) -> io::Result<()> {
+    let mut encoder = GifEncoder::new(BufWriter::new(File::create(path)?));
+    render_frames(trees, app_config, |image| {
+        let delay = Delay::from_numer_denom_ms(1000, EXPORT_FPS.round() as u32);
+        encoder
+            .encode_frame(Frame::from_parts(image.clone(), 0, 0, delay))
+            .map_err(to_io_error)
+    })
+}
+
+// writes the growth animation as numbered png files next to the given path,
+// growth.png becomes growth_00000.png, growth_00001.png, ...
+pub fn write_png_sequence(
+    path: &Path,
+    trees: &[LsystemTree],
+    app_config: &AppConfig,
+) -> io::Result<()> {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("frame")
+        .to_string();
+
+    let mut frame_i = 0;
+    render_frames(trees, app_config, |image| {
+        let frame_path = path.with_file_name(format!("{}_{:05}.png", stem, frame_i));
+        frame_i += 1;
+        image.save(frame_path).map_err(to_io_error)
+    })
+}
+
+// plays the animation with the same scheduling as the viewer with a fixed frame rate and gives
+// every frame to on_frame until all trees are grown, nothing is written, if the animation would
+// never end or would have more than MAX_EXPORT_FRAMES frames
+fn render_frames(
+    trees: &[LsystemTree],
+    app_config: &AppConfig,
+    mut on_frame: impl FnMut(&RgbaImage) -> io::Result<()>,
+) -> io::Result<()> {
+    // the progress in dots, that is added in every frame
+    let progress_pro_frame = app_config.dots_pro_second / EXPORT_FPS;
+    if progress_pro_frame.is_nan() || progress_pro_frame <= 0.0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "The animation never ends with dots_pro_second {}",
+                app_config.dots_pro_second
+            ),
+        ));
+    }
+
+    let max_branches = MAX_ANIMATED_BRANCHES / trees.len().max(1);
+    let animations = trees
+        .iter()
+        .map(|tree| TreeAnimation::new(tree, max_branches, app_config.scheduling))
+        .collect::<Vec<TreeAnimation>>();
+
+    // the frames, after which all trees are finished
+    let duration = animations
+        .iter()
+        .map(|animation| animation.duration())
+        .max()
+        .unwrap_or(0);
+    let frames = (duration as f32 / progress_pro_frame).ceil() as usize;
+    if frames > MAX_EXPORT_FRAMES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "The animation has {} frames, more than {}, raise dots_pro_second",
+                frames, MAX_EXPORT_FRAMES
+            ),
+        ));
+    }
+
+    let bg = rgb_bytes(&app_config.bg_color);
+    let (min, max) = bounds(trees);
+    // the grown branches, the polygons, the circles and the shapes are drawn once on the base, in
+    // the frame where they are finished, every frame is the copy of the base with the growing
+    // branches, so their edges are not blended again in every frame
+    let mut base = Canvas::new(min, max, bg);
+    let mut progress = 0.0;
+
+    let mut grown_counts = vec![0; trees.len()];
+    // the progress, to which the polygons, the circles and the shapes are drawn, below zero for
+    // the ones on the root
+    let mut shapes_progress = -1.0;
+
+    for _ in 0..frames {
+        progress += progress_pro_frame;
+
+        for ((tree, animation), grown_count) in trees
+            .iter()
+            .zip(animations.iter())
+            .zip(grown_counts.iter_mut())
+        {
+            let grown = animation.grown_branches_at(progress);
+            for id in grown[*grown_count..].iter() {
+                base.stroke(&tree.branch_stroke(id));
+            }
+            *grown_count = grown.len();
+
+            for polygon in tree.polygons.iter() {
+                if let Some(start) = animation.polygon_start(polygon) {
+                    if start > shapes_progress && start <= progress {
+                        base.polygon(polygon);
+                    }
+                }
+            }
+            for circle in tree.circles.iter() {
+                if let Some(start) = animation.circle_start(circle) {
+                    if start > shapes_progress && start <= progress {
+                        base.circle(circle);
+                    }
+                }
+            }
+            for instance in tree.shape_instances.iter() {
+                if let (Some(start), Some(outline)) = (
+                    animation.shape_start(instance),
+                    tree.shapes.get(&instance.shape),
+                ) {
+                    if start > shapes_progress && start <= progress {
+                        base.shape(outline, instance);
+                    }
+                }
+            }
+        }
+        shapes_progress = progress;
+
+        let mut frame = base.clone();
+        for (tree, animation) in trees.iter().zip(animations.iter()) {
+            for (_, stroke) in animation.visible_branches(tree, progress) {
+                frame.stroke(&stroke);
+            }
+        }
+        on_frame(&frame.image)?;
+    }
+
+    Ok(())
+}
+
+fn to_io_error(err: ImageError) -> io::Error {
+    io::Error::other(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_trees;
+
+    fn count_frames(dots_pro_second: f32) -> io::Result<usize> {
+        let (trees, mut app_config) = test_trees();
+        let trees = trees.into_iter().map(|tree| tree.tree).collect::<Vec<_>>();
+        app_config.dots_pro_second = dots_pro_second;
+        let mut frames = 0;
+        render_frames(&trees, &app_config, |_| {
+            frames += 1;
+            Ok(())
+        })?;
+        Ok(frames)
+    }
+
+    #[test]
+    fn renders_until_all_trees_are_grown() {
+        let frames = count_frames(EXPORT_FPS).unwrap();
+        // one dot pro frame
+        let (trees, app_config) = test_trees();
+        let max_branches = MAX_ANIMATED_BRANCHES / trees.len().max(1);
+        let duration = trees
+            .iter()
+            .map(|tree| TreeAnimation::new(&tree.tree, max_branches, app_config.scheduling))
+            .map(|animation| animation.duration())
+            .max()
+            .unwrap();
+        assert!(duration > 0);
+        assert_eq!(frames, duration);
+    }
+
+    #[test]
+    fn rejects_a_never_ending_animation() {
+        assert!(count_frames(0.0).is_err());
+        assert!(count_frames(-5.0).is_err());
+        assert!(count_frames(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn growing_branch_has_the_edges_of_the_grown_one() {
+        let mut app_config = AppConfig::new(vec![0]);
+        app_config.config.axiom = "FFFFFFFF".to_string();
+        app_config.config.start_direction = nannou::geom::pt2(0.0, 10.0);
+        app_config.config.line_weight = 3.0;
+        app_config.dots_pro_second = EXPORT_FPS;
+        let tree = crate::lsystem::LsystemBuilder::new(&app_config.config).build_tree_3d(&0);
+
+        let mut frames = vec![];
+        render_frames(&[tree.tree], &app_config, |image| {
+            frames.push(image.clone());
+            Ok(())
+        })
+        .unwrap();
+        let (growing, grown) = (&frames[frames.len() / 2], frames.last().unwrap());
+
+        // the pixels on the edges of the trunk near its root, that are neither the background
+        // nor the color of the branch
+        let bg = rgb_bytes(&app_config.bg_color);
+        let color = rgb_bytes(&app_config.config.main_color);
+        let bottom = grown.height() - 25;
+        let edges = (0..grown.width())
+            .map(|x| (x, bottom))
+            .filter(|(x, y)| {
+                let pixel = grown.get_pixel(*x, *y);
+                let rgb = (pixel[0], pixel[1], pixel[2]);
+                rgb != bg && rgb != color
+            })
+            .collect::<Vec<_>>();
+        assert!(!edges.is_empty());
+        for (x, y) in edges {
+            assert_eq!(growing.get_pixel(x, y), grown.get_pixel(x, y));
+        }
+    }
+
+    #[test]
+    fn rejects_too_many_frames() {
+        assert!(count_frames(1e-6).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use nannou::glam::{Mat3, Quat, Vec3};
+use serde_json::{json, Value};
+
+use super::{
+    color_bytes,
+    mesh::{branches_mesh, leaf_mesh, polygons_mesh, shape_mesh, Mesh},
+};
+use crate::lsystem::LsystemTree3D;
+
+// the constants of the gltf specification
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const TRIANGLES: u32 = 4;
+
+// writes the trees as a binary gltf (glb), the branches and the polygons of every tree are one
+// mesh each, the leaves are the instances of one disc mesh for every color and the shapes of one
+// mesh for every shape and color
+pub fn write(path: &Path, trees: &[LsystemTree3D], segments: usize) -> io::Result<()> {
+    fs::write(path, to_glb(trees, segments))
+}
+
+fn to_glb(trees: &[LsystemTree3D], segments: usize) -> Vec<u8> {
+    let mut gltf = Gltf::new();
+    let mut scene_nodes = vec![];
+    let mut leaf_meshes: HashMap<(u8, u8, u8), usize> = HashMap::new();
+    let mut shape_meshes: HashMap<(String, (u8, u8, u8)), usize> = HashMap::new();
+
+    for (i, tree) in trees.iter().enumerate() {
+        let meshes = [
+            (
+                format!("tree_{}_branches", i),
+                branches_mesh(tree, segments),
+            ),
+            (format!("tree_{}_polygons", i), polygons_mesh(tree)),
+        ];
+        for (name, mesh) in meshes {
+            if !mesh.indices.is_empty() {
+                let mesh_i = gltf.push_mesh(&name, &mesh);
+                scene_nodes.push(gltf.push_node(json!({ "name": name, "mesh": mesh_i })));
+            }
+        }
+
+        for (j, circle) in tree.tree.circles.iter().enumerate() {
+            let color = color_bytes(circle.color);
+            let mesh_i = *leaf_meshes.entry(color).or_insert_with(|| {
+                let name = format!("leaf_{:02X}{:02X}{:02X}", color.0, color.1, color.2);
+                gltf.push_mesh(&name, &leaf_mesh(segments, circle.color))
+            });
+            scene_nodes.push(gltf.push_node(json!({
+                "name": format!("tree_{}_leaf_{}", i, j),
+                "mesh": mesh_i,
+                "translation": [circle.pos.x, circle.pos.y, circle.z],
+                "scale": [circle.radius, circle.radius, 1.0],
+            })));
+        }
+
+        for (j, instance) in tree.tree.shape_instances.iter().enumerate() {
+            let Some(outline) = tree.tree.shapes.get(&instance.shape) else {
+                continue;
+            };
+            let color = color_bytes(instance.color);
+            let mesh_i = *shape_meshes
+                .entry((instance.shape.clone(), color))
+                .or_insert_with(|| {
+                    let name = format!(
+                        "{}_{:02X}{:02X}{:02X}",
+                        instance.shape, color.0, color.1, color.2
+                    );
+                    gltf.push_mesh(&name, &shape_mesh(outline, instance.color))
+                });
+            // the x and y of the shape go to the right and the heading of the turtle
+            let rotation = Quat::from_mat3(&Mat3::from_cols(
+                instance.right,
+                instance.heading,
+                instance.right.cross(instance.heading),
+            ));
+            scene_nodes.push(gltf.push_node(json!({
+                "name": format!("tree_{}_{}_{}", i, instance.shape, j),
+                "mesh": mesh_i,
+                "translation": [instance.pos.x, instance.pos.y, instance.z],
+                "rotation": <[f32; 4]>::from(rotation.normalize()),
+                "scale": [instance.scale, instance.scale, instance.scale],
+            })));
+        }
+    }
+
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "trees_bg" },
+        "scene": 0,
+        "scenes": [{ "nodes": scene_nodes }],
+        "nodes": gltf.nodes,
+        "meshes": gltf.meshes,
+        "accessors": gltf.accessors,
+        "bufferViews": gltf.buffer_views,
+        "buffers": [{ "byteLength": gltf.bin.len() }],
+    });
+
+    // the both chunks are padded to 4 bytes, the json with spaces
+    let mut json = document.to_string().into_bytes();
+    json.resize(json.len().div_ceil(4) * 4, b' ');
+    let mut bin = gltf.bin;
+    bin.resize(bin.len().div_ceil(4) * 4, 0);
+
+    let mut out = vec![];
+    out.extend(b"glTF");
+    out.extend(2u32.to_le_bytes());
+    out.extend(((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+    out.extend((json.len() as u32).to_le_bytes());
+    out.extend(b"JSON");
+    out.extend(json);
+    out.extend((bin.len() as u32).to_le_bytes());
+    out.extend(b"BIN\0");
+    out.extend(bin);
+    out
+}
+
+// the binary buffer and the parts of the json, that point into it
+struct Gltf {
+    bin: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    nodes: Vec<Value>,
+}
+
+impl Gltf {
+    fn new() -> Gltf {
+        Gltf {
+            bin: vec![],
+            buffer_views: vec![],
+            accessors: vec![],
+            meshes: vec![],
+            nodes: vec![],
+        }
+    }
+
+    // adds the mesh and gives its index
+    fn push_mesh(&mut self, name: &str, mesh: &Mesh) -> usize {
+        let positions = self.push_vec3s(&mesh.positions);
+        let normals = self.push_vec3s(&mesh.normals);
+        // the colors in gltf are linear
+        let colors = mesh
+            .colors
+            .iter()
+            .map(|color| {
+                let linear = color.into_linear();
+                Vec3::new(linear.red, linear.green, linear.blue)
+            })
+            .collect::<Vec<Vec3>>();
+        let colors = self.push_vec3s(&colors);
+        let indices = self.push_indices(&mesh.indices);
+
+        self.meshes.push(json!({
+            "name": name,
+            "primitives": [{
+                "attributes": { "POSITION": positions, "NORMAL": normals, "COLOR_0": colors },
+                "indices": indices,
+                "mode": TRIANGLES,
+            }],
+        }));
+        self.meshes.len() - 1
+    }
+
+    fn push_node(&mut self, node: Value) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    // adds the vectors with their bounds, that are needed for the positions
+    fn push_vec3s(&mut self, vectors: &[Vec3]) -> usize {
+        let mut min = Vec3::splat(f32::MAX);
+        let mut max = Vec3::splat(f32::MIN);
+        for vector in vectors.iter() {
+            min = min.min(*vector);
+            max = max.max(*vector);
+        }
+
+        let bytes = vectors
+            .iter()
+            .flat_map(|vector| vector.to_array())
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let view = self.push_view(bytes, ARRAY_BUFFER);
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": vectors.len(),
+            "type": "VEC3",
+            "min": min.to_array(),
+            "max": max.to_array(),
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn push_indices(&mut self, indices: &[u32]) -> usize {
+        let bytes = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.push_view(bytes, ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+
+    // all data is of 4 bytes, so the views stay aligned
+    fn push_view(&mut self, bytes: Vec<u8>, target: u32) -> usize {
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.bin.extend(bytes);
+        self.buffer_views.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_trees;
+
+    fn u32_at(bytes: &[u8], i: usize) -> usize {
+        u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()) as usize
+    }
+
+    // the json and the binary chunk of the glb
+    fn chunks(glb: &[u8]) -> (Value, &[u8]) {
+        assert_eq!(&glb[..4], b"glTF");
+        assert_eq!(u32_at(glb, 4), 2);
+        assert_eq!(u32_at(glb, 8), glb.len());
+
+        let json_len = u32_at(glb, 12);
+        assert_eq!(&glb[16..20], b"JSON");
+        let json = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        let bin_start = 20 + json_len;
+        assert_eq!(&glb[bin_start + 4..bin_start + 8], b"BIN\0");
+        let bin = &glb[bin_start + 8..bin_start + 8 + u32_at(glb, bin_start)];
+        assert_eq!(bin_start + 8 + bin.len(), glb.len());
+        (json, bin)
+    }
+
+    #[test]
+    fn views_and_accessors_fit_in_the_buffer() {
+        let (trees, app_config) = test_trees();
+        let glb = to_glb(&trees, app_config.mesh_segments);
+        let (json, bin) = chunks(&glb);
+        assert!(json["buffers"][0]["byteLength"].as_u64().unwrap() as usize <= bin.len());
+
+        let views = json["bufferViews"].as_array().unwrap();
+        for view in views {
+            let offset = view["byteOffset"].as_u64().unwrap() as usize;
+            assert_eq!(offset % 4, 0);
+            assert!(offset + view["byteLength"].as_u64().unwrap() as usize <= bin.len());
+        }
+        for accessor in json["accessors"].as_array().unwrap() {
+            let view = &views[accessor["bufferView"].as_u64().unwrap() as usize];
+            let components = if accessor["type"] == "VEC3" { 3 } else { 1 };
+            assert_eq!(
+                accessor["count"].as_u64().unwrap() * components * 4,
+                view["byteLength"].as_u64().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn nodes_point_to_meshes_with_unit_rotations() {
+        let (trees, app_config) = test_trees();
+        let (json, _) = chunks(&to_glb(&trees, app_config.mesh_segments));
+        let meshes = json["meshes"].as_array().unwrap().len();
+        let nodes = json["nodes"].as_array().unwrap();
+        assert_eq!(
+            json["scenes"][0]["nodes"].as_array().unwrap().len(),
+            nodes.len()
+        );
+
+        let mut rotations = 0;
+        for node in nodes {
+            assert!((node["mesh"].as_u64().unwrap() as usize) < meshes);
+            if let Some(rotation) = node["rotation"].as_array() {
+                let length = rotation
+                    .iter()
+                    .map(|value| value.as_f64().unwrap().powi(2))
+                    .sum::<f64>();
+                assert!((length - 1.0).abs() < 1e-4);
+                rotations += 1;
+            }
+        }
+        // the leaf shape of the both trees
+        assert_eq!(rotations, 2);
+    }
+}
@@ -0,0 +1,309 @@
+use std::f32::consts::PI;
+
+use nannou::{
+    color::Rgb,
+    geom::{pt3, Point2, Point3},
+    glam::Vec3,
+};
+
+use crate::lsystem::LsystemTree3D;
+
+// a triangle mesh with the normal and the color of every vertex
+pub struct Mesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub colors: Vec<Rgb>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh {
+            positions: vec![],
+            normals: vec![],
+            colors: vec![],
+            indices: vec![],
+        }
+    }
+
+    fn push_vertex(&mut self, position: Point3, normal: Vec3, color: Rgb) -> u32 {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.colors.push(color);
+        (self.positions.len() - 1) as u32
+    }
+}
+
+// all branches of the tree as generalized cylinders in one mesh
+pub fn branches_mesh(tree: &LsystemTree3D, segments: usize) -> Mesh {
+    let mut mesh = Mesh::new();
+    for id in tree.tree.branch_ids() {
+        let stroke = tree.tree.branch_stroke(&id);
+        let radii = stroke
+            .widths
+            .iter()
+            .map(|width| width / 2.0)
+            .collect::<Vec<f32>>();
+        add_tube(
+            &mut mesh,
+            &tree.branch_points(&id),
+            &radii,
+            &stroke.colors,
+            segments,
+        );
+    }
+    mesh
+}
+
+// all polygons of the tree in one mesh, every polygon is a fan from its first point, that is
+// enough for the convex leaves and petals
+pub fn polygons_mesh(tree: &LsystemTree3D) -> Mesh {
+    let mut mesh = Mesh::new();
+    for polygon in tree.tree.polygons.iter() {
+        let points = polygon
+            .points
+            .iter()
+            .zip(polygon.points_z.iter())
+            .map(|(point, z)| point.extend(*z))
+            .collect::<Vec<Point3>>();
+
+        // the normal by the newell's method, so it works for the not flat polygons too
+        let mut normal = Vec3::ZERO;
+        for (i, a) in points.iter().enumerate() {
+            normal += a.cross(points[(i + 1) % points.len()]);
+        }
+        let normal = normal.normalize_or_zero();
+
+        let first = mesh.positions.len() as u32;
+        for point in points.iter() {
+            mesh.push_vertex(*point, normal, polygon.color);
+        }
+        for i in 1..points.len() as u32 - 1 {
+            mesh.indices.extend([first, first + i, first + i + 1]);
+        }
+    }
+    mesh
+}
+
+// a disc with the radius 1 around the origin facing to +z, the leaves are its instances
+pub fn leaf_mesh(segments: usize, color: Rgb) -> Mesh {
+    let mut mesh = Mesh::new();
+    let center = mesh.push_vertex(pt3(0.0, 0.0, 0.0), Vec3::Z, color);
+    for i in 0..segments {
+        let angle = 2.0 * PI * i as f32 / segments as f32;
+        mesh.push_vertex(pt3(angle.cos(), angle.sin(), 0.0), Vec3::Z, color);
+    }
+    for i in 0..segments as u32 {
+        mesh.indices
+            .extend([center, 1 + i, 1 + (i + 1) % segments as u32]);
+    }
+    mesh
+}
+
+// the outline of a shape in the xy plane facing to +z as a fan from its first point, the stamped
+// shapes are its instances
+pub fn shape_mesh(outline: &[Point2], color: Rgb) -> Mesh {
+    let mut mesh = Mesh::new();
+    if outline.len() < 3 {
+        return mesh;
+    }
+    for point in outline.iter() {
+        mesh.push_vertex(point.extend(0.0), Vec3::Z, color);
+    }
+    for i in 1..outline.len() as u32 - 1 {
+        mesh.indices.extend([0, i, i + 1]);
+    }
+    mesh
+}
+
+// a tube along the points, the rings are turned along the tube by the parallel transport, so the
+// tube doesn't twist, the both ends are closed by flat caps
+fn add_tube(mesh: &mut Mesh, points: &[Point3], radii: &[f32], colors: &[Rgb], segments: usize) {
+    // the dots on the same place have no direction
+    let mut dots: Vec<(Point3, f32, Rgb)> = vec![];
+    for ((point, radius), color) in points.iter().zip(radii.iter()).zip(colors.iter()) {
+        if dots
+            .last()
+            .is_none_or(|last| last.0.distance(*point) > f32::EPSILON)
+        {
+            dots.push((*point, *radius, *color));
+        }
+    }
+    if dots.len() < 2 || segments < 3 {
+        return;
+    }
+
+    // the tangent on every dot, between the both segments
+    let last = dots.len() - 1;
+    let tangents = (0..dots.len())
+        .map(|i| {
+            let segment = dots[i.min(last - 1) + 1].0 - dots[i.min(last - 1)].0;
+            let tangent = dots[i.min(last - 1) + 1].0 - dots[i.max(1) - 1].0;
+            // on a turn around the both segments cancel out, so the ring is across the segment
+            if tangent.length() > 1e-3 * segment.length() {
+                tangent.normalize()
+            } else {
+                segment.normalize()
+            }
+        })
+        .collect::<Vec<Vec3>>();
+
+    let mut normal = any_normal(tangents[0]);
+    let first_ring = mesh.positions.len() as u32;
+    for ((point, radius, color), tangent) in dots.iter().zip(tangents.iter()) {
+        // the normal of the previous ring, turned in the plane of this ring, if the tangent turned
+        // to the previous normal, there is no turned one and the ring starts with any normal
+        let turned = normal - *tangent * normal.dot(*tangent);
+        normal = if turned.length() > 1e-3 {
+            turned.normalize()
+        } else {
+            any_normal(*tangent)
+        };
+        let binormal = tangent.cross(normal);
+        for j in 0..segments {
+            let angle = 2.0 * PI * j as f32 / segments as f32;
+            let out = normal * angle.cos() + binormal * angle.sin();
+            mesh.push_vertex(*point + out * *radius, out, *color);
+        }
+    }
+
+    let segments_u32 = segments as u32;
+    for i in 0..last as u32 {
+        let ring = first_ring + i * segments_u32;
+        let next_ring = ring + segments_u32;
+        for j in 0..segments_u32 {
+            let next_j = (j + 1) % segments_u32;
+            mesh.indices
+                .extend([ring + j, ring + next_j, next_ring + j]);
+            mesh.indices
+                .extend([ring + next_j, next_ring + next_j, next_ring + j]);
+        }
+    }
+
+    add_cap(
+        mesh,
+        &dots[last],
+        tangents[last],
+        first_ring + last as u32 * segments_u32,
+        segments,
+    );
+    add_cap(mesh, &dots[0], -tangents[0], first_ring, segments);
+}
+
+// any normal of the direction, crossed with the axis, that is the farthest from it
+fn any_normal(direction: Vec3) -> Vec3 {
+    let helper = if direction.x.abs() < 0.9 {
+        Vec3::X
+    } else {
+        Vec3::Y
+    };
+    direction.cross(helper).normalize()
+}
+
+// closes the ring of the tube with a flat disc facing to the normal
+fn add_cap(
+    mesh: &mut Mesh,
+    (point, _, color): &(Point3, f32, Rgb),
+    normal: Vec3,
+    ring: u32,
+    segments: usize,
+) {
+    let center = mesh.push_vertex(*point, normal, *color);
+    // the cap has its own vertices for the flat shading
+    let first = mesh.positions.len() as u32;
+    for j in 0..segments as u32 {
+        let position = mesh.positions[(ring + j) as usize];
+        mesh.push_vertex(position, normal, *color);
+    }
+
+    // the rings go counterclockwise around the tangent, so the cap on the start, that faces
+    // against it, is turned
+    let (a, b) = (
+        mesh.positions[first as usize],
+        mesh.positions[first as usize + 1],
+    );
+    let turned = (a - *point).cross(b - *point).dot(normal) < 0.0;
+    let segments = segments as u32;
+    for j in 0..segments {
+        let (a, b) = (first + j, first + (j + 1) % segments);
+        if turned {
+            mesh.indices.extend([center, b, a]);
+        } else {
+            mesh.indices.extend([center, a, b]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::color::rgb;
+
+    use super::*;
+    use crate::export::test_trees;
+
+    fn tube(points: &[Point3]) -> Mesh {
+        let mut mesh = Mesh::new();
+        let radii = vec![1.0; points.len()];
+        let colors = vec![rgb(0.0, 0.0, 0.0); points.len()];
+        add_tube(&mut mesh, points, &radii, &colors, 6);
+        mesh
+    }
+
+    fn assert_unit_normals(mesh: &Mesh) {
+        for normal in mesh.normals.iter() {
+            assert!((normal.length() - 1.0).abs() < 1e-4, "{:?}", normal);
+        }
+    }
+
+    #[test]
+    fn tube_has_a_ring_on_every_dot_and_two_caps() {
+        let mesh = tube(&[pt3(0.0, 0.0, 0.0), pt3(0.0, 1.0, 0.0), pt3(1.0, 2.0, 0.5)]);
+        // 3 rings and a center and a ring for every cap
+        assert_eq!(mesh.positions.len(), 3 * 6 + 2 * 7);
+        assert_eq!(mesh.indices.len(), 3 * (2 * 2 * 6 + 2 * 6));
+        assert!(mesh
+            .indices
+            .iter()
+            .all(|i| (*i as usize) < mesh.positions.len()));
+        assert_unit_normals(&mesh);
+    }
+
+    #[test]
+    fn tube_along_the_axes_and_turning_around_has_normals() {
+        // along the x axis, where the first helper axis doesn't work
+        assert_unit_normals(&tube(&[pt3(0.0, 0.0, 0.0), pt3(1.0, 0.0, 0.0)]));
+        // the turn around, where the both segments cancel out
+        assert_unit_normals(&tube(&[
+            pt3(0.0, 0.0, 0.0),
+            pt3(0.0, 1.0, 0.0),
+            pt3(0.0, 0.0, 0.0),
+        ]));
+        // the tangent turns to the normal of the previous ring
+        assert_unit_normals(&tube(&[
+            pt3(0.0, 0.0, 0.0),
+            pt3(0.0, 0.0, 1.0),
+            pt3(0.0, 1.0, 1.0),
+            pt3(0.0, 1.0, 2.0),
+        ]));
+    }
+
+    #[test]
+    fn every_mesh_of_the_trees_is_valid() {
+        let (trees, app_config) = test_trees();
+        for tree in trees.iter() {
+            for mesh in [
+                branches_mesh(tree, app_config.mesh_segments),
+                polygons_mesh(tree),
+            ] {
+                assert!(!mesh.indices.is_empty());
+                assert_eq!(mesh.indices.len() % 3, 0);
+                assert!(mesh
+                    .indices
+                    .iter()
+                    .all(|i| (*i as usize) < mesh.positions.len()));
+                assert!(mesh.positions.iter().all(|position| position.is_finite()));
+                assert_unit_normals(&mesh);
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+mod dxf;
+mod frames;
+mod gltf;
+mod mesh;
+mod obj;
+mod pdf;
+mod raster;
+mod svg;
+
+use std::{io, path::Path};
+
+use nannou::{
+    color::Rgb,
+    geom::{pt2, Point2},
+};
+
+use crate::{
+    config::AppConfig,
+    lsystem::{LsystemTree, LsystemTree3D},
+};
+
+// exports the trees in the file, the format is chosen by the extension of the path
+pub fn export_trees(
+    path: &Path,
+    trees_3d: &[LsystemTree3D],
+    app_config: &AppConfig,
+) -> io::Result<()> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    // the 2D formats draw the trees from the front
+    let trees = &trees_3d
+        .iter()
+        .map(|tree_3d| tree_3d.tree.clone())
+        .collect::<Vec<LsystemTree>>();
+
+    match extension.as_deref() {
+        // the 3D meshes
+        Some("obj") => obj::write(path, trees_3d, app_config.mesh_segments),
+        Some("glb") => gltf::write(path, trees_3d, app_config.mesh_segments),
+        Some("dxf") => dxf::write(path, trees, &app_config.config),
+        Some("pdf") => pdf::write(path, trees),
+        Some("svg") => svg::write(path, trees),
+        // the growth animation
+        Some("gif") => frames::write_gif(path, trees, app_config),
+        Some("png") => frames::write_png_sequence(path, trees, app_config),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown export format of {}, use .dxf, .pdf, .svg, .gif, .png, .obj or .glb",
+                path.display()
+            ),
+        )),
+    }
+}
+
+// writes the trees as they are seen in the viewer as an svg
+pub fn export_svg(path: &Path, trees: &[LsystemTree]) -> io::Result<()> {
+    svg::write(path, trees)
+}
+
+// the lower left and the upper right corner of all trees together
+fn bounds(trees: &[LsystemTree]) -> (Point2, Point2) {
+    let mut min = pt2(f32::MAX, f32::MAX);
+    let mut max = pt2(f32::MIN, f32::MIN);
+    for tree in trees {
+        let (tree_min, tree_max) = tree.bounds();
+        min = min.min(tree_min);
+        max = max.max(tree_max);
+    }
+    if trees.is_empty() {
+        return (pt2(0.0, 0.0), pt2(0.0, 0.0));
+    }
+    (min, max)
+}
+
+// the color as 0..=255 channels
+fn rgb_bytes(hex: &str) -> (u8, u8, u8) {
+    color_bytes(crate::misc::hex_to_rgb(hex))
+}
+
+fn color_bytes(color: Rgb) -> (u8, u8, u8) {
+    (
+        (color.red * 255.0).round() as u8,
+        (color.green * 255.0).round() as u8,
+        (color.blue * 255.0).round() as u8,
+    )
+}
+
+// two small trees with branches of several orders, a polygon, a dot and a leaf for the tests of
+// the exporters
+#[cfg(test)]
+fn test_trees() -> (Vec<LsystemTree3D>, AppConfig) {
+    let mut app_config = AppConfig::new(vec![2, 3]);
+    app_config.config.axiom = "F[+F@][-F{.F.-F.}]&FL".to_string();
+    let trees = app_config
+        .deeps
+        .iter()
+        .map(|deep| crate::lsystem::LsystemBuilder::new(&app_config.config).build_tree_3d(deep))
+        .collect();
+    (trees, app_config)
+}
@@ -0,0 +1,127 @@
+use std::{fs, io, path::Path};
+
+use super::mesh::{branches_mesh, leaf_mesh, polygons_mesh, shape_mesh, Mesh};
+use crate::lsystem::LsystemTree3D;
+
+// writes the trees as a wavefront obj, the branches and the polygons of every tree are one object
+// each and every leaf and shape is an own object, the vertices have their color after the position, as blender reads it
+pub fn write(path: &Path, trees: &[LsystemTree3D], segments: usize) -> io::Result<()> {
+    fs::write(path, to_obj(trees, segments))
+}
+
+fn to_obj(trees: &[LsystemTree3D], segments: usize) -> String {
+    let mut out = String::new();
+    // the indices in obj start with 1 and go through the whole file
+    let mut offset = 1;
+
+    for (i, tree) in trees.iter().enumerate() {
+        let mesh = branches_mesh(tree, segments);
+        write_object(
+            &mut out,
+            &format!("tree_{}_branches", i),
+            &mesh,
+            &mut offset,
+        );
+        let mesh = polygons_mesh(tree);
+        write_object(
+            &mut out,
+            &format!("tree_{}_polygons", i),
+            &mesh,
+            &mut offset,
+        );
+
+        for (j, circle) in tree.tree.circles.iter().enumerate() {
+            let mut leaf = leaf_mesh(segments, circle.color);
+            for position in leaf.positions.iter_mut() {
+                *position = circle.pos.extend(circle.z) + *position * circle.radius;
+            }
+            write_object(
+                &mut out,
+                &format!("tree_{}_leaf_{}", i, j),
+                &leaf,
+                &mut offset,
+            );
+        }
+
+        for (j, instance) in tree.tree.shape_instances.iter().enumerate() {
+            let Some(outline) = tree.tree.shapes.get(&instance.shape) else {
+                continue;
+            };
+            let mut mesh = shape_mesh(outline, instance.color);
+            let normal = instance.right.cross(instance.heading);
+            for (position, original) in mesh.positions.iter_mut().zip(outline.iter()) {
+                *position = instance.transform_3d(*original);
+            }
+            mesh.normals.iter_mut().for_each(|n| *n = normal);
+            write_object(
+                &mut out,
+                &format!("tree_{}_{}_{}", i, instance.shape, j),
+                &mesh,
+                &mut offset,
+            );
+        }
+    }
+
+    out
+}
+
+fn write_object(out: &mut String, name: &str, mesh: &Mesh, offset: &mut usize) {
+    if mesh.indices.is_empty() {
+        return;
+    }
+
+    out.push_str(&format!("o {}\n", name));
+    for (position, color) in mesh.positions.iter().zip(mesh.colors.iter()) {
+        out.push_str(&format!(
+            "v {} {} {} {:.4} {:.4} {:.4}\n",
+            position.x, position.y, position.z, color.red, color.green, color.blue
+        ));
+    }
+    for normal in mesh.normals.iter() {
+        out.push_str(&format!("vn {} {} {}\n", normal.x, normal.y, normal.z));
+    }
+    // every vertex has its own normal, so they have the same index
+    for triangle in mesh.indices.chunks(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize + *offset);
+        out.push_str(&format!("f {a}//{a} {b}//{b} {c}//{c}\n"));
+    }
+    *offset += mesh.positions.len();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_trees;
+
+    #[test]
+    fn faces_point_to_the_vertices_before_them() {
+        let (trees, app_config) = test_trees();
+        let obj = to_obj(&trees, app_config.mesh_segments);
+
+        let (mut vertices, mut normals, mut objects) = (0, 0, 0);
+        for line in obj.lines() {
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("o") => objects += 1,
+                Some("v") => {
+                    // the position and the color
+                    assert_eq!(parts.count(), 6);
+                    vertices += 1;
+                }
+                Some("vn") => normals += 1,
+                Some("f") => {
+                    for corner in parts {
+                        let (vertex, normal) = corner.split_once("//").unwrap();
+                        let vertex = vertex.parse::<usize>().unwrap();
+                        assert!(vertex >= 1 && vertex <= vertices);
+                        assert_eq!(normal.parse::<usize>().unwrap(), vertex);
+                    }
+                }
+                _ => panic!("unknown line {}", line),
+            }
+        }
+        assert_eq!(vertices, normals);
+        // the branches, the polygons, the dot and the leaf of both trees
+        assert_eq!(objects, 2 * 4);
+    }
+}
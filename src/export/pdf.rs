@@ -0,0 +1,170 @@
+use std::{fs, io, path::Path};
+
+use super::{bounds, color_bytes};
+use crate::lsystem::LsystemTree;
+
+// the free space around the trees on the page
+const MARGIN: f32 = 20.0;
+
+// writes the trees on a single pdf page, the page is as big as the trees plus the margin
+pub fn write(path: &Path, trees: &[LsystemTree]) -> io::Result<()> {
+    fs::write(path, to_pdf(trees))
+}
+
+fn to_pdf(trees: &[LsystemTree]) -> Vec<u8> {
+    let (min, max) = bounds(trees);
+    let width = max.x - min.x + 2.0 * MARGIN;
+    let height = max.y - min.y + 2.0 * MARGIN;
+
+    let content = content_stream(trees, MARGIN - min.x, MARGIN - min.y);
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents 4 0 R >>",
+            width, height
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            content.len(),
+            content
+        ),
+    ];
+
+    let mut out = String::from("%PDF-1.4\n");
+    // the byte offset of every object for the xref table
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref_offset = out.len();
+    out.push_str(&format!("xref\n0 {}\n", objects.len() + 1));
+    out.push_str("0000000000 65535 f \n");
+    for offset in offsets {
+        out.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    out.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    ));
+
+    out.into_bytes()
+}
+
+// the drawing commands, every part of a branch with the same color, every polygon, circle and
+// shape is filled as its outline
+fn content_stream(trees: &[LsystemTree], dx: f32, dy: f32) -> String {
+    let mut out = String::new();
+    // the fill color is only set, when it changes
+    let mut last_color = None;
+
+    let shapes = trees.iter().flat_map(|tree| {
+        let runs = tree.branch_ids().into_iter().flat_map(move |id| {
+            tree.branch_stroke(&id)
+                .color_runs()
+                .into_iter()
+                .map(|(run, color)| (run.outline(), color))
+        });
+        let circles = tree
+            .circles
+            .iter()
+            .map(|circle| (circle.outline(), circle.color));
+        let polygons = tree
+            .polygons
+            .iter()
+            .map(|polygon| (polygon.points.clone(), polygon.color));
+        let instances = tree
+            .shape_instances
+            .iter()
+            .map(move |instance| (tree.shape_outline(instance), instance.color));
+        runs.chain(polygons).chain(circles).chain(instances)
+    });
+    for (outline, color) in shapes {
+        if outline.is_empty() {
+            continue;
+        }
+
+        let (r, g, b) = color_bytes(color);
+        if last_color != Some((r, g, b)) {
+            out.push_str(&format!(
+                "{:.3} {:.3} {:.3} rg\n",
+                r as f32 / 255.0,
+                g as f32 / 255.0,
+                b as f32 / 255.0,
+            ));
+            last_color = Some((r, g, b));
+        }
+
+        out.push_str(&format!(
+            "{:.2} {:.2} m\n",
+            outline[0].x + dx,
+            outline[0].y + dy
+        ));
+        for point in outline[1..].iter() {
+            out.push_str(&format!("{:.2} {:.2} l\n", point.x + dx, point.y + dy));
+        }
+        // closed and filled with the non zero rule
+        out.push_str("h f\n");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_trees;
+
+    fn pdf() -> String {
+        let (trees, _) = test_trees();
+        let trees = trees.into_iter().map(|tree| tree.tree).collect::<Vec<_>>();
+        String::from_utf8(to_pdf(&trees)).unwrap()
+    }
+
+    #[test]
+    fn xref_points_to_the_objects() {
+        let pdf = pdf();
+        let xref_offset = pdf
+            .lines()
+            .skip_while(|line| *line != "startxref")
+            .nth(1)
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert!(pdf[xref_offset..].starts_with("xref\n0 5\n"));
+
+        let offsets = pdf[xref_offset..].lines().skip(3).take(4);
+        for (i, offset) in offsets.enumerate() {
+            let offset = offset[..10].parse::<usize>().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+        assert!(pdf.ends_with("%%EOF\n"));
+    }
+
+    #[test]
+    fn stream_has_its_length_and_closed_paths() {
+        let pdf = pdf();
+        let start = pdf.find("stream\n").unwrap() + "stream\n".len();
+        let end = pdf.find("\nendstream").unwrap();
+        let length = pdf
+            .split("/Length ")
+            .nth(1)
+            .and_then(|rest| rest.split(' ').next())
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert_eq!(end - start, length);
+
+        let content = &pdf[start..end];
+        // every path is started, closed and filled
+        assert_eq!(
+            content.matches(" m\n").count(),
+            content.matches("h f\n").count()
+        );
+        assert!(content.contains(" rg\n"));
+    }
+}
@@ -0,0 +1,142 @@
+use nannou::{
+    geom::{pt2, Point2},
+    image::{Rgba, RgbaImage},
+};
+
+use super::color_bytes;
+use crate::lsystem::{Circle, Polygon, ShapeInstance, Stroke};
+
+// the free space around the trees in the image
+const MARGIN: f32 = 20.0;
+// how many lines are sampled in every pixel row
+const SAMPLES: usize = 4;
+
+// a small software renderer for the offline exports, so that no window is needed
+#[derive(Clone)]
+pub struct Canvas {
+    pub image: RgbaImage,
+    // the lower left corner of the drawn area in the world coordinates
+    min: Point2,
+}
+
+impl Canvas {
+    pub fn new(min: Point2, max: Point2, bg: (u8, u8, u8)) -> Canvas {
+        let width = (max.x - min.x + 2.0 * MARGIN).ceil().max(1.0) as u32;
+        let height = (max.y - min.y + 2.0 * MARGIN).ceil().max(1.0) as u32;
+        Canvas {
+            image: RgbaImage::from_pixel(width, height, Rgba([bg.0, bg.1, bg.2, 255])),
+            min,
+        }
+    }
+
+    pub fn stroke(&mut self, stroke: &Stroke) {
+        for (run, color) in stroke.color_runs() {
+            self.fill_polygon(&run.outline(), color_bytes(color));
+        }
+    }
+
+    pub fn circle(&mut self, circle: &Circle) {
+        self.fill_polygon(&circle.outline(), color_bytes(circle.color));
+    }
+
+    pub fn polygon(&mut self, polygon: &Polygon) {
+        self.fill_polygon(&polygon.points, color_bytes(polygon.color));
+    }
+
+    // the shape on its place, the outline is given by the tree
+    pub fn shape(&mut self, outline: &[Point2], instance: &ShapeInstance) {
+        let points = outline
+            .iter()
+            .map(|point| instance.transform(*point))
+            .collect::<Vec<Point2>>();
+        self.fill_polygon(&points, color_bytes(instance.color));
+    }
+
+    // fills the polygon with the non zero rule, every pixel row is sampled on some lines for the
+    // antialiasing
+    pub fn fill_polygon(&mut self, polygon: &[Point2], color: (u8, u8, u8)) {
+        if polygon.len() < 3 {
+            return;
+        }
+        let points = polygon
+            .iter()
+            .map(|point| self.to_pixel(*point))
+            .collect::<Vec<Point2>>();
+
+        let (mut min, mut max) = (points[0], points[0]);
+        for point in points.iter() {
+            min = min.min(*point);
+            max = max.max(*point);
+        }
+        let x_from = min.x.floor().max(0.0) as usize;
+        let y_from = min.y.floor().max(0.0) as usize;
+        let x_to = (max.x.ceil().max(0.0) as usize).min(self.image.width() as usize);
+        let y_to = (max.y.ceil().max(0.0) as usize).min(self.image.height() as usize);
+        if x_from >= x_to || y_from >= y_to {
+            return;
+        }
+
+        let width = x_to - x_from;
+        let mut coverage = vec![0.0; width * (y_to - y_from)];
+        for y in y_from..y_to {
+            for sample in 0..SAMPLES {
+                let sample_y = y as f32 + (sample as f32 + 0.5) / SAMPLES as f32;
+
+                // where the edges cross the line and in which direction
+                let mut crossings = vec![];
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    if (a.y <= sample_y) != (b.y <= sample_y) {
+                        let x = a.x + (sample_y - a.y) / (b.y - a.y) * (b.x - a.x);
+                        crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for i in 0..crossings.len().saturating_sub(1) {
+                    winding += crossings[i].1;
+                    if winding == 0 {
+                        continue;
+                    }
+                    let (span_from, span_to) = (crossings[i].0, crossings[i + 1].0);
+                    let row = (y - y_from) * width;
+                    let px_from = (span_from.floor().max(x_from as f32) as usize).max(x_from);
+                    let px_to = (span_to.ceil().max(0.0) as usize).min(x_to);
+                    for x in px_from..px_to {
+                        let covered = span_to.min(x as f32 + 1.0) - span_from.max(x as f32);
+                        coverage[row + x - x_from] += covered.max(0.0) / SAMPLES as f32;
+                    }
+                }
+            }
+        }
+
+        for (i, covered) in coverage.into_iter().enumerate() {
+            if covered > 0.0 {
+                let x = (x_from + i % width) as u32;
+                let y = (y_from + i / width) as u32;
+                self.blend(x, y, color, covered.min(1.0));
+            }
+        }
+    }
+
+    // the world is y up, the image is y down
+    fn to_pixel(&self, point: Point2) -> Point2 {
+        pt2(
+            point.x - self.min.x + MARGIN,
+            self.image.height() as f32 - (point.y - self.min.y + MARGIN),
+        )
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: (u8, u8, u8), coverage: f32) {
+        let pixel = self.image.get_pixel_mut(x, y);
+        let mix =
+            |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * coverage).round() as u8;
+        *pixel = Rgba([
+            mix(pixel[0], color.0),
+            mix(pixel[1], color.1),
+            mix(pixel[2], color.2),
+            255,
+        ]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use nannou::{color::Rgb, geom::Point2};
+
+use super::{bounds, color_bytes};
+use crate::lsystem::LsystemTree;
+
+// the free space around the trees in the image
+const MARGIN: f32 = 20.0;
+
+// writes the trees as an svg, every shape is defined once and its instances are <use> elements
+pub fn write(path: &Path, trees: &[LsystemTree]) -> io::Result<()> {
+    fs::write(path, to_svg(trees))
+}
+
+fn to_svg(trees: &[LsystemTree]) -> String {
+    let (min, max) = bounds(trees);
+    let width = max.x - min.x + 2.0 * MARGIN;
+    let height = max.y - min.y + 2.0 * MARGIN;
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+         width=\"{:.2}\" height=\"{:.2}\" viewBox=\"0 0 {:.2} {:.2}\">\n",
+        width, height, width, height
+    );
+
+    // the outlines without a fill, so the instances give their own color
+    let shapes = trees
+        .iter()
+        .flat_map(|tree| tree.shapes.iter())
+        .collect::<BTreeMap<&String, &Vec<Point2>>>();
+    out.push_str("<defs>\n");
+    for (name, outline) in shapes {
+        out.push_str(&format!(
+            "<path id=\"{}\" d=\"{}\"/>\n",
+            shape_id(name),
+            path_data(outline)
+        ));
+    }
+    out.push_str("</defs>\n");
+
+    // the world is y up, the svg is y down, so everything is in the flipped group
+    out.push_str(&format!(
+        "<g transform=\"matrix(1 0 0 -1 {:.2} {:.2})\">\n",
+        MARGIN - min.x,
+        max.y + MARGIN
+    ));
+    for tree in trees {
+        for id in tree.branch_ids() {
+            for (run, color) in tree.branch_stroke(&id).color_runs() {
+                filled_path(&mut out, &run.outline(), color);
+            }
+        }
+        for polygon in tree.polygons.iter() {
+            filled_path(&mut out, &polygon.points, polygon.color);
+        }
+        for circle in tree.circles.iter() {
+            out.push_str(&format!(
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\"/>\n",
+                circle.pos.x,
+                circle.pos.y,
+                circle.radius,
+                hex(circle.color)
+            ));
+        }
+        for instance in tree.shape_instances.iter() {
+            out.push_str(&format!(
+                "<use xlink:href=\"#{}\" transform=\"translate({:.2} {:.2}) rotate({:.2}) \
+                 scale({:.3})\" fill=\"{}\"/>\n",
+                shape_id(&instance.shape),
+                instance.pos.x,
+                instance.pos.y,
+                instance.rotation.to_degrees(),
+                instance.scale,
+                hex(instance.color)
+            ));
+        }
+    }
+    out.push_str("</g>\n</svg>\n");
+
+    out
+}
+
+fn filled_path(out: &mut String, outline: &[Point2], color: Rgb) {
+    if outline.is_empty() {
+        return;
+    }
+    out.push_str(&format!(
+        "<path d=\"{}\" fill=\"{}\"/>\n",
+        path_data(outline),
+        hex(color)
+    ));
+}
+
+// the closed polyline through the points
+fn path_data(outline: &[Point2]) -> String {
+    let mut data = outline
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let command = if i == 0 { "M" } else { "L" };
+            format!("{}{:.3} {:.3}", command, point.x, point.y)
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    data.push_str(" Z");
+    data
+}
+
+// the names of the shapes are given by the user, so only the safe chars stay in the id
+fn shape_id(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect::<String>();
+    format!("shape_{}", name)
+}
+
+fn hex(color: Rgb) -> String {
+    let (r, g, b) = color_bytes(color);
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_trees;
+
+    fn svg() -> String {
+        let (trees, _) = test_trees();
+        let trees = trees.into_iter().map(|tree| tree.tree).collect::<Vec<_>>();
+        to_svg(&trees)
+    }
+
+    #[test]
+    fn every_use_points_to_a_defined_shape() {
+        let svg = svg();
+        let defs = &svg[svg.find("<defs>").unwrap()..svg.find("</defs>").unwrap()];
+        let uses = svg.matches("<use xlink:href=\"#").count();
+        // the leaf of the both trees
+        assert_eq!(uses, 2);
+        for part in svg.split("<use xlink:href=\"#").skip(1) {
+            let id = &part[..part.find('"').unwrap()];
+            assert!(defs.contains(&format!("<path id=\"{}\"", id)));
+        }
+    }
+
+    #[test]
+    fn elements_are_closed_and_filled() {
+        let svg = svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+        for line in svg.lines().filter(|line| line.starts_with("<path d=")) {
+            assert!(line.contains(" Z\" fill=\"#"));
+            assert!(line.ends_with("/>"));
+        }
+        assert_eq!(svg.matches("<circle ").count(), 2);
+    }
+
+    #[test]
+    fn shape_ids_have_only_safe_chars() {
+        assert_eq!(shape_id("leaf"), "shape_leaf");
+        assert_eq!(shape_id("big \"leaf\"<1>"), "shape_big__leaf__1_");
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+
+use nannou::{
+    lyon::{
+        math::point,
+        path::Path,
+        tessellation::{
+            BuffersBuilder, FillOptions, FillRule, FillTessellator, FillVertex, VertexBuffers,
+        },
+    },
+    prelude::*,
+};
+
+use crate::{
+    animation::TreeAnimation,
+    lsystem::{BranchDot, LsystemTree, Stroke},
+};
+
+// how many layers the depths of the 3D trees are split into
+pub const DEPTH_LAYERS: usize = 16;
+
+// the depths of the dots of all trees split into the layers, that are drawn from the far to the
+// near one, the 2D trees have everything in the first layer
+#[derive(Clone, Copy, PartialEq)]
+pub struct DepthLayers {
+    min: f32,
+    max: f32,
+}
+
+impl DepthLayers {
+    pub fn new<'a>(trees: impl IntoIterator<Item = &'a LsystemTree>) -> DepthLayers {
+        let (mut min, mut max) = (f32::MAX, f32::MIN);
+        for tree in trees {
+            for dot in tree.branches.values().flatten() {
+                min = min.min(dot.z);
+                max = max.max(dot.z);
+            }
+        }
+        DepthLayers { min, max }
+    }
+
+    // the bigger z is nearer, so it is in the later layer
+    pub fn layer(&self, z: f32) -> usize {
+        if self.max - self.min <= f32::EPSILON {
+            return 0;
+        }
+        let fraction = (z - self.min) / (self.max - self.min);
+        ((fraction * DEPTH_LAYERS as f32) as usize).min(DEPTH_LAYERS - 1)
+    }
+}
+
+// the fully grown branches of a tree, tessellated only once when they are finished, so they stay
+// visible and are drawn as one mesh for every depth layer instead of a polyline for every branch
+pub struct GrownMesh {
+    // the finished branches are appended to the mesh of their layer, so the 2D trees keep the
+    // order of growing and the near branches are drawn over the far ones
+    layers: Vec<MeshLayer>,
+    // how many of the grown branches of the animation are already in the mesh
+    branches_count: usize,
+}
+
+#[derive(Default)]
+struct MeshLayer {
+    points: Vec<Point3>,
+    // the color and the anchor of every point
+    colors: Vec<Rgb>,
+    anchors: Vec<Anchor>,
+    indices: Vec<usize>,
+}
+
+// the place on the branch, with which a point of the mesh moves, so the wind can bend the mesh
+// like the dots of the branch without tessellating it again
+#[derive(Clone, Copy, Default)]
+pub struct Anchor {
+    pub pos: Point2,
+    pub arc_length: f32,
+    pub width: f32,
+}
+
+impl Anchor {
+    fn of(dot: &BranchDot) -> Anchor {
+        Anchor {
+            pos: dot.pos,
+            arc_length: dot.arc_length,
+            width: dot.width,
+        }
+    }
+}
+
+impl GrownMesh {
+    pub fn new() -> GrownMesh {
+        GrownMesh {
+            layers: (0..DEPTH_LAYERS).map(|_| MeshLayer::default()).collect(),
+            branches_count: 0,
+        }
+    }
+
+    // adds the branches, that were finished since the last update, the tree is the one without
+    // the wind, the layers have to be the same until the mesh is built again
+    pub fn update(
+        &mut self,
+        tree: &LsystemTree,
+        animation: &TreeAnimation,
+        progress: f32,
+        depth_layers: &DepthLayers,
+    ) {
+        let grown = animation.grown_branches_at(progress);
+        // the animation was rewinded, so the mesh is built again
+        if grown.len() < self.branches_count {
+            *self = GrownMesh::new();
+        }
+
+        if grown.len() == self.branches_count {
+            return;
+        }
+
+        for id in grown[self.branches_count..].iter() {
+            let layer = &mut self.layers[depth_layers.layer(tree.branch_depth(id))];
+            // the dots of the stroke, the fork dot first
+            let dots = tree
+                .fork_dot(id)
+                .into_iter()
+                .chain(tree.branches[id].iter().cloned())
+                .collect::<Vec<BranchDot>>();
+            let stroke = tree.branch_stroke(id);
+            for (range, color) in stroke.color_run_ranges() {
+                let run = Stroke {
+                    points: stroke.points[range.clone()].to_vec(),
+                    widths: stroke.widths[range.clone()].to_vec(),
+                    colors: stroke.colors[range.clone()].to_vec(),
+                };
+                let outline = run
+                    .outline_with_sources()
+                    .into_iter()
+                    .map(|(point, i)| (point, Anchor::of(&dots[range.start() + i])))
+                    .collect::<Vec<(Point2, Anchor)>>();
+                tessellate_outline(&outline, layer);
+                layer.colors.resize(layer.points.len(), color);
+            }
+        }
+        self.branches_count = grown.len();
+    }
+
+    // draws the mesh of the depth layer with every point moved by the offset of its anchor
+    pub fn draw_layer(&self, i: usize, draw: &Draw, offset: impl Fn(&Anchor) -> Point2) {
+        let layer = &self.layers[i];
+        if layer.indices.is_empty() {
+            return;
+        }
+        let points = layer
+            .points
+            .iter()
+            .zip(layer.anchors.iter())
+            .map(|(point, anchor)| *point + offset(anchor).extend(0.0));
+        draw.mesh().indexed_colored(
+            points.zip(layer.colors.iter().cloned()),
+            layer.indices.iter().cloned(),
+        );
+    }
+}
+
+// the tessellated outline of a shape, it is built once for all instances of the shape and placed
+// by the transform of the draw
+pub struct ShapeMesh {
+    points: Vec<Point3>,
+    indices: Vec<usize>,
+}
+
+impl ShapeMesh {
+    pub fn new(outline: &[Point2]) -> ShapeMesh {
+        let outline = outline
+            .iter()
+            .map(|point| (*point, Anchor::default()))
+            .collect::<Vec<(Point2, Anchor)>>();
+        let mut layer = MeshLayer::default();
+        tessellate_outline(&outline, &mut layer);
+        ShapeMesh {
+            points: layer.points,
+            indices: layer.indices,
+        }
+    }
+
+    // the meshes of all shapes of the tree by their names
+    pub fn of_tree(tree: &LsystemTree) -> HashMap<String, ShapeMesh> {
+        tree.shapes
+            .iter()
+            .map(|(name, outline)| (name.clone(), ShapeMesh::new(outline)))
+            .collect()
+    }
+
+    pub fn draw(&self, draw: &Draw, color: Rgb) {
+        if self.indices.is_empty() {
+            return;
+        }
+        draw.mesh()
+            .indexed(self.points.iter().cloned(), self.indices.iter().cloned())
+            .color(color);
+    }
+}
+
+// draws one stroke as its filled outline, every part of the same color separately
+pub fn draw_stroke(draw: &Draw, stroke: &Stroke) {
+    for (run, color) in stroke.color_runs() {
+        let outline = run
+            .outline()
+            .into_iter()
+            .map(|point| (point, Anchor::default()))
+            .collect::<Vec<(Point2, Anchor)>>();
+        let mut layer = MeshLayer::default();
+        tessellate_outline(&outline, &mut layer);
+        if !layer.indices.is_empty() {
+            draw.mesh()
+                .indexed(layer.points, layer.indices)
+                .color(color);
+        }
+    }
+}
+
+// tessellates the filled outline and appends it to the points, anchors and indices of the layer,
+// the non zero rule keeps the places, where the outline overlaps itself on the sharp turns,
+// filled, the anchors of the new points on the crossings are interpolated
+fn tessellate_outline(outline: &[(Point2, Anchor)], layer: &mut MeshLayer) {
+    if outline.len() < 3 {
+        return;
+    }
+
+    let attributes =
+        |anchor: &Anchor| [anchor.pos.x, anchor.pos.y, anchor.arc_length, anchor.width];
+    let mut builder = Path::builder_with_attributes(4);
+    let (first, first_anchor) = outline[0];
+    builder.begin(point(first.x, first.y), &attributes(&first_anchor));
+    for (p, anchor) in outline[1..].iter() {
+        builder.line_to(point(p.x, p.y), &attributes(anchor));
+    }
+    builder.end(true);
+    let path = builder.build();
+
+    let options = FillOptions::default().with_fill_rule(FillRule::NonZero);
+    let mut buffers: VertexBuffers<(Point3, Anchor), u32> = VertexBuffers::new();
+    let res = FillTessellator::new().tessellate_path(
+        &path,
+        &options,
+        &mut BuffersBuilder::new(&mut buffers, |mut vertex: FillVertex| {
+            let position = vertex.position();
+            let anchor = match vertex.interpolated_attributes() {
+                &[x, y, arc_length, width] => Anchor {
+                    pos: pt2(x, y),
+                    arc_length,
+                    width,
+                },
+                _ => Anchor::default(),
+            };
+            (pt3(position.x, position.y, 0.0), anchor)
+        }),
+    );
+    if res.is_err() {
+        return;
+    }
+
+    let offset = layer.points.len();
+    for (point, anchor) in buffers.vertices {
+        layer.points.push(point);
+        layer.anchors.push(anchor);
+    }
+    layer
+        .indices
+        .extend(buffers.indices.iter().map(|i| offset + *i as usize));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{animation::Scheduling, config::AppConfig, lsystem::LsystemBuilder};
+
+    fn tree() -> LsystemTree {
+        let mut app_config = AppConfig::new(vec![2]);
+        app_config.config.axiom = "X".to_string();
+        LsystemBuilder::new(&app_config.config)
+            .build_tree_3d(&2)
+            .tree
+    }
+
+    fn points_count(mesh: &GrownMesh) -> usize {
+        mesh.layers.iter().map(|layer| layer.points.len()).sum()
+    }
+
+    #[test]
+    fn finished_branches_are_appended() {
+        let tree = tree();
+        let animation = TreeAnimation::new(&tree, 4, Scheduling::Queue);
+        let layers = DepthLayers::new([&tree]);
+        let mut mesh = GrownMesh::new();
+        let mut last_count = 0;
+        for progress in 0..=animation.duration() {
+            mesh.update(&tree, &animation, progress as f32, &layers);
+            let count = points_count(&mesh);
+            assert!(count >= last_count);
+            last_count = count;
+        }
+        assert_eq!(mesh.branches_count, tree.branches.len());
+
+        // the rewinded animation builds the mesh again
+        mesh.update(&tree, &animation, 0.0, &layers);
+        assert_eq!(points_count(&mesh), 0);
+    }
+
+    #[test]
+    fn every_point_has_the_anchor_of_its_branch() {
+        let tree = tree();
+        let animation = TreeAnimation::new(&tree, 4, Scheduling::Queue);
+        let layers = DepthLayers::new([&tree]);
+        let mut mesh = GrownMesh::new();
+        mesh.update(&tree, &animation, animation.duration() as f32, &layers);
+
+        let max_arc = tree
+            .branches
+            .values()
+            .flatten()
+            .map(|dot| dot.arc_length)
+            .fold(0.0, f32::max);
+        for layer in mesh.layers.iter() {
+            assert_eq!(layer.points.len(), layer.anchors.len());
+            assert_eq!(layer.points.len(), layer.colors.len());
+            for (point, anchor) in layer.points.iter().zip(layer.anchors.iter()) {
+                // the point is around its dot on the outline of the branch
+                assert!(point.truncate().distance(anchor.pos) <= anchor.width + 1e-3);
+                assert!(anchor.arc_length >= 0.0 && anchor.arc_length <= max_arc + 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn shapes_are_tessellated_once_by_name() {
+        let meshes = ShapeMesh::of_tree(&tree());
+        let leaf = &meshes["leaf"];
+        assert!(!leaf.indices.is_empty());
+        assert_eq!(leaf.indices.len() % 3, 0);
+        assert!(leaf.indices.iter().all(|i| *i < leaf.points.len()));
+    }
+}
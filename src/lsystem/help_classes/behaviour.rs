@@ -2,23 +2,72 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum Behaviour {
     DrawForward,
+    // moves the turtle without drawing, the branch goes on from the new place
+    MoveForward,
     RotateLeft,
     RotateRight,
+    // turns the turtle by 180 degrees
+    TurnAround,
+    // the rotations of the 3D turtle around its left and heading directions, the RotateLeft and
+    // RotateRight turn it around its up direction
+    PitchDown,
+    PitchUp,
+    RollLeft,
+    RollRight,
     Branch,
     BranchStop,
+    // changes the width of the following segments by the step, in the multiples of the width,
+    // that the width mode gives
+    IncrementWidth(f32),
+    DecrementWidth(f32),
+    // changes the color of the turtle to the palette color with the index
+    SetColor(usize),
+    // the next color of the palette
+    NextColor,
+    // multiplies the step length by the factor
+    ScaleStep(f32),
+    // draws a filled circle with the radius (scaled like the step) on the place of the turtle
+    DrawDot(f32),
+    // starts a polygon, records the place of the turtle as its vertex and closes it, the
+    // polygons can be nested
+    StartPolygon,
+    PolygonVertex,
+    EndPolygon,
+    // stamps the shape with the name on the place of the turtle, turned by its heading and
+    // scaled like the step
+    StampShape(String),
+    // does nothing, for the symbols, that are only needed by the rules
+    NoOp,
 }
 
 impl Display for Behaviour {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let text = match self {
-            Behaviour::DrawForward => "Create",
-            Behaviour::RotateLeft => "RotateLeft",
-            Behaviour::RotateRight => "RotateRight",
-            Behaviour::Branch => "Branch",
-            Behaviour::BranchStop => "BranchStop",
+            Behaviour::DrawForward => "Create".to_string(),
+            Behaviour::MoveForward => "MoveForward".to_string(),
+            Behaviour::RotateLeft => "RotateLeft".to_string(),
+            Behaviour::RotateRight => "RotateRight".to_string(),
+            Behaviour::TurnAround => "TurnAround".to_string(),
+            Behaviour::PitchDown => "PitchDown".to_string(),
+            Behaviour::PitchUp => "PitchUp".to_string(),
+            Behaviour::RollLeft => "RollLeft".to_string(),
+            Behaviour::RollRight => "RollRight".to_string(),
+            Behaviour::Branch => "Branch".to_string(),
+            Behaviour::BranchStop => "BranchStop".to_string(),
+            Behaviour::IncrementWidth(step) => format!("IncrementWidth({})", step),
+            Behaviour::DecrementWidth(step) => format!("DecrementWidth({})", step),
+            Behaviour::SetColor(i) => format!("SetColor({})", i),
+            Behaviour::NextColor => "NextColor".to_string(),
+            Behaviour::ScaleStep(factor) => format!("ScaleStep({})", factor),
+            Behaviour::DrawDot(radius) => format!("DrawDot({})", radius),
+            Behaviour::StartPolygon => "StartPolygon".to_string(),
+            Behaviour::PolygonVertex => "PolygonVertex".to_string(),
+            Behaviour::EndPolygon => "EndPolygon".to_string(),
+            Behaviour::StampShape(name) => format!("StampShape({})", name),
+            Behaviour::NoOp => "NoOp".to_string(),
         };
 
         write!(f, "{}", text)
@@ -1,7 +1,17 @@
-use nannou::geom::Point2;
+use nannou::{color::Rgb, geom::Point2};
 
 #[derive(Clone, Debug)]
 pub struct BranchDot {
     pub pos: Point2,
+    // the z of the dot, it's 0 for the 2D trees
+    pub z: f32,
     pub connected_branches_id: Vec<usize>,
+    // the distance from the root of the tree along the branches
+    pub arc_length: f32,
+    // the width of the segment, that ends on this dot
+    pub width: f32,
+    // the factor of the width, that was changed by the width symbols
+    pub width_scale: f32,
+    // the color of the segment, that ends on this dot
+    pub color: Rgb,
 }
@@ -0,0 +1,35 @@
+use std::f32::consts::PI;
+
+use nannou::{
+    color::Rgb,
+    geom::{pt2, Point2},
+};
+
+// how many segments the outline of a circle has
+const CIRCLE_SEGMENTS: usize = 16;
+
+// a filled circle, drawn by the DrawDot symbol
+#[derive(Clone, Debug)]
+pub struct Circle {
+    pub pos: Point2,
+    // the z of the center, it's 0 for the 2D trees
+    pub z: f32,
+    pub radius: f32,
+    pub color: Rgb,
+    // the branch, on which the turtle was, and how many dots of it were drawn before, so the
+    // circle appears, when the branch has grown to it
+    pub branch_id: usize,
+    pub i_on_branch: usize,
+}
+
+impl Circle {
+    // the circle as a polygon, for the renderers, that fill only polygons
+    pub fn outline(&self) -> Vec<Point2> {
+        (0..CIRCLE_SEGMENTS)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f32 / CIRCLE_SEGMENTS as f32;
+                self.pos + pt2(angle.cos(), angle.sin()) * self.radius
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Display;
+
+use nannou::color::{rgb, Rgb};
+use serde::{Deserialize, Serialize};
+
+use crate::misc::hex_to_rgb;
+
+// how the branches are colored
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ColorMode {
+    // every branch has the main color
+    Single,
+    // the palette color, that was chosen by the SetColor symbols
+    Symbols,
+    // gradients through the palette from the root to the farthest dot:
+    // by the count of steps, by the distance along the branches and by the branch order
+    Depth,
+    ArcLength,
+    Order,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ColorScheme {
+    pub mode: ColorMode,
+    // "#RRGGBB" colors, indexed by the SetColor symbols or the stops of the gradient
+    pub palette: Vec<String>,
+}
+
+impl ColorScheme {
+    pub fn new(mode: ColorMode, palette: &[&str]) -> ColorScheme {
+        ColorScheme {
+            mode,
+            palette: palette.iter().map(|hex| hex.to_string()).collect(),
+        }
+    }
+
+    pub fn palette_colors(&self) -> Vec<Rgb> {
+        self.palette.iter().map(|hex| hex_to_rgb(hex)).collect()
+    }
+}
+
+// the color on the place t (0..=1) of the gradient through the colors
+pub fn gradient(colors: &[Rgb], t: f32) -> Rgb {
+    match colors.len() {
+        0 => rgb(0.0, 0.0, 0.0),
+        1 => colors[0],
+        len => {
+            let scaled = t.clamp(0.0, 1.0) * (len - 1) as f32;
+            let i = (scaled.floor() as usize).min(len - 2);
+            let (from, to) = (colors[i], colors[i + 1]);
+            let fraction = scaled - i as f32;
+            rgb(
+                from.red + (to.red - from.red) * fraction,
+                from.green + (to.green - from.green) * fraction,
+                from.blue + (to.blue - from.blue) * fraction,
+            )
+        }
+    }
+}
+
+impl Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            ColorMode::Single => "Single",
+            ColorMode::Symbols => "Symbols",
+            ColorMode::Depth => "Depth",
+            ColorMode::ArcLength => "ArcLength",
+            ColorMode::Order => "Order",
+        };
+
+        write!(f, "{}", text)
+    }
+}
+
+impl Display for ColorScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:?}", self.mode, self.palette)
+    }
+}
@@ -0,0 +1,66 @@
+use std::{f32::consts::PI, fmt::Display};
+
+use nannou::rand::{rngs::StdRng, Rng};
+use serde::{Deserialize, Serialize};
+
+// how the random deviations are distributed
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Distribution {
+    // evenly between -spread and spread
+    Uniform,
+    // around 0 with the spread as the standard deviation
+    Normal,
+}
+
+// the random deviations of the turtle, so the same grammar doesn't give the same artificial
+// geometry, the seed makes them reproducible
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Jitter {
+    // the spread of every turn in radian
+    pub angle: f32,
+    // the spread of every step as the part of its length
+    pub length: f32,
+    pub distribution: Distribution,
+    pub seed: u64,
+}
+
+impl Jitter {
+    pub fn is_enabled(&self) -> bool {
+        self.angle != 0.0 || self.length != 0.0
+    }
+
+    // a random deviation with the spread
+    pub fn sample(&self, rng: &mut StdRng, spread: f32) -> f32 {
+        if spread == 0.0 {
+            return 0.0;
+        }
+        match self.distribution {
+            Distribution::Uniform => rng.gen_range(-spread..=spread),
+            Distribution::Normal => {
+                // the box-muller transform, the first number must not be 0 for the ln
+                let u1 = 1.0 - rng.gen::<f32>();
+                let u2 = rng.gen::<f32>();
+                (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos() * spread
+            }
+        }
+    }
+}
+
+impl Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Distribution::Uniform => write!(f, "Uniform"),
+            Distribution::Normal => write!(f, "Normal"),
+        }
+    }
+}
+
+impl Display for Jitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Angle:{} Length:{} Distribution:{} Seed:{}",
+            self.angle, self.length, self.distribution, self.seed
+        )
+    }
+}
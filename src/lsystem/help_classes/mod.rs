@@ -1,9 +1,23 @@
 mod behaviour;
 mod branch_dot;
+mod circle;
+mod color_scheme;
+mod jitter;
+mod polygon;
 mod rule;
 mod rules;
+mod shape;
+mod stroke;
+mod width_mode;
 
 pub use behaviour::Behaviour;
 pub use branch_dot::BranchDot;
+pub use circle::Circle;
+pub use color_scheme::{gradient, ColorMode, ColorScheme};
+pub use jitter::{Distribution, Jitter};
+pub use polygon::Polygon;
 pub use rule::Rule;
 pub use rules::Rules;
+pub use shape::{Shape, ShapeInstance, ShapeSource};
+pub use stroke::Stroke;
+pub use width_mode::WidthMode;
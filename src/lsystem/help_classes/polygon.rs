@@ -0,0 +1,21 @@
+use nannou::{color::Rgb, geom::Point2};
+
+// a filled polygon of the { . } symbols, for the leaves and the petals
+#[derive(Clone, Debug)]
+pub struct Polygon {
+    pub points: Vec<Point2>,
+    // the z of every point, it's 0 for the 2D trees
+    pub points_z: Vec<f32>,
+    pub color: Rgb,
+    // the branch, on which the polygon was started, and how many dots of it were drawn before,
+    // so the polygon appears, when the branch has grown to it
+    pub branch_id: usize,
+    pub i_on_branch: usize,
+}
+
+impl Polygon {
+    // the mean z of the points
+    pub fn depth(&self) -> f32 {
+        self.points_z.iter().sum::<f32>() / self.points_z.len().max(1) as f32
+    }
+}
@@ -17,20 +17,54 @@ impl Rules {
         )
     }
 
+    // adds the rule or changes the rule of its symbol
+    pub fn insert(&mut self, rule: Rule) {
+        self.0.insert(rule.0, (rule.1, rule.2));
+    }
+
+    // every symbol with its text and behaviour
+    pub fn iter(&self) -> impl Iterator<Item = (&char, &String, &Behaviour)> {
+        self.0.iter().map(|(ch, (text, beh))| (ch, text, beh))
+    }
+
     pub fn get_text(&self, ch: &char) -> Option<&String> {
-        if let Some((text, _)) = self.0.get(&ch) {
-            return Some(text);
-        } else {
-            return None;
+        self.0.get(ch).map(|(text, _)| text)
+    }
+
+    // the count of the symbols, that the text becomes after the rules are applied deep times, the
+    // counting stops as soon as there are more than max
+    pub fn symbols_count(&self, text: &str, deep: usize, max: usize) -> usize {
+        // the count of the symbols, that every symbol becomes, the symbols without a rule stay
+        let mut counts = self
+            .0
+            .keys()
+            .map(|ch| (*ch, 1))
+            .collect::<HashMap<char, usize>>();
+        let count_of = |counts: &HashMap<char, usize>, text: &str| {
+            text.chars()
+                .map(|ch| counts.get(&ch).copied().unwrap_or(1))
+                .fold(0, usize::saturating_add)
+        };
+        for _ in 0..deep {
+            if count_of(&counts, text) > max {
+                break;
+            }
+            let next = self
+                .0
+                .iter()
+                .map(|(ch, (rule_text, _))| (*ch, count_of(&counts, rule_text)))
+                .collect::<HashMap<char, usize>>();
+            // the counts don't change anymore
+            if next == counts {
+                break;
+            }
+            counts = next;
         }
+        count_of(&counts, text)
     }
 
     pub fn get_behaviour(&self, ch: &char) -> Option<&Behaviour> {
-        if let Some((_, beh)) = self.0.get(&ch) {
-            return Some(beh);
-        } else {
-            return None;
-        }
+        self.0.get(ch).map(|(_, beh)| beh)
     }
 }
 
@@ -0,0 +1,233 @@
+use std::fmt::Display;
+
+use nannou::{
+    color::Rgb,
+    geom::{pt2, Point2, Point3},
+    glam::Vec3,
+};
+use serde::{Deserialize, Serialize};
+
+// how many segments a curve of the svg path has
+const CURVE_SEGMENTS: usize = 8;
+
+// where the outline of a shape comes from
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ShapeSource {
+    Points(Vec<(f32, f32)>),
+    // the path data of svg ("M 0 0 C ... Z"), the curves are flattened
+    SvgPath(String),
+}
+
+// a named shape, that is stamped on the place of the turtle. It's in the units of the step, the
+// turtle is on the origin and heads to +y
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Shape {
+    pub name: String,
+    pub source: ShapeSource,
+}
+
+impl Shape {
+    pub fn new(name: &str, source: ShapeSource) -> Shape {
+        Shape {
+            name: name.to_string(),
+            source,
+        }
+    }
+
+    pub fn outline(&self) -> Vec<Point2> {
+        self.try_outline()
+            .unwrap_or_else(|| panic!("The svg path of the shape {} is wrong", self.name))
+    }
+
+    // None, if the svg path is wrong
+    pub fn try_outline(&self) -> Option<Vec<Point2>> {
+        match &self.source {
+            ShapeSource::Points(points) => Some(points.iter().map(|(x, y)| pt2(*x, *y)).collect()),
+            ShapeSource::SvgPath(path) => parse_svg_path(path),
+        }
+    }
+}
+
+// a stamped shape, the outline of the shape is kept once in the tree
+#[derive(Clone, Debug)]
+pub struct ShapeInstance {
+    pub shape: String,
+    pub pos: Point2,
+    // the z of the place, it's 0 for the 2D trees
+    pub z: f32,
+    // the directions of the x and y of the shape in 3D, the right and the heading of the turtle
+    pub right: Vec3,
+    pub heading: Vec3,
+    // the angle of the y of the shape on the screen from +y in radian
+    pub rotation: f32,
+    pub scale: f32,
+    pub color: Rgb,
+    // the branch, on which the turtle was, and how many dots of it were drawn before, so the
+    // shape appears, when the branch has grown to it
+    pub branch_id: usize,
+    pub i_on_branch: usize,
+}
+
+impl ShapeInstance {
+    // the angle of the heading on the screen, the heading along z keeps the rotation
+    pub fn rotation_of(heading: Point2, rotation: f32) -> f32 {
+        if heading.length() > f32::EPSILON {
+            (-heading.x).atan2(heading.y)
+        } else {
+            rotation
+        }
+    }
+
+    // the point of the shape on its place in the tree
+    pub fn transform(&self, point: Point2) -> Point2 {
+        let (sin, cos) = self.rotation.sin_cos();
+        self.pos + pt2(point.x * cos - point.y * sin, point.x * sin + point.y * cos) * self.scale
+    }
+
+    // the point of the shape on its place in the 3D tree
+    pub fn transform_3d(&self, point: Point2) -> Point3 {
+        self.pos.extend(self.z) + (self.right * point.x + self.heading * point.y) * self.scale
+    }
+}
+
+// the points of the path with the commands M, L, H, V, C, Q and Z (absolute and relative), all
+// subpaths are put together in one outline
+fn parse_svg_path(path: &str) -> Option<Vec<Point2>> {
+    let tokens = tokenize(path)?;
+    let mut points: Vec<Point2> = vec![];
+    let mut current = pt2(0.0, 0.0);
+    let mut start = current;
+    let mut command = 'M';
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if let Token::Command(ch) = tokens[i] {
+            command = ch;
+            i += 1;
+            if command.eq_ignore_ascii_case(&'z') {
+                current = start;
+                continue;
+            }
+        }
+
+        let count = match command.to_ascii_uppercase() {
+            'M' | 'L' => 2,
+            'H' | 'V' => 1,
+            'Q' => 4,
+            'C' => 6,
+            _ => return None,
+        };
+        let mut numbers = vec![];
+        for _ in 0..count {
+            match tokens.get(i) {
+                Some(Token::Number(number)) => numbers.push(*number),
+                _ => return None,
+            }
+            i += 1;
+        }
+
+        // the relative points are from the current point
+        let origin = if command.is_ascii_lowercase() {
+            current
+        } else {
+            pt2(0.0, 0.0)
+        };
+        let point = |j: usize| origin + pt2(numbers[j], numbers[j + 1]);
+        match command.to_ascii_uppercase() {
+            'M' => {
+                current = point(0);
+                start = current;
+                points.push(current);
+                // the next pairs without a command are lines
+                command = if command == 'm' { 'l' } else { 'L' };
+            }
+            'L' => {
+                current = point(0);
+                points.push(current);
+            }
+            'H' => {
+                current = pt2(origin.x + numbers[0], current.y);
+                points.push(current);
+            }
+            'V' => {
+                current = pt2(current.x, origin.y + numbers[0]);
+                points.push(current);
+            }
+            'Q' => {
+                let (from, control, to) = (current, point(0), point(2));
+                points.extend((1..=CURVE_SEGMENTS).map(|s| {
+                    let t = s as f32 / CURVE_SEGMENTS as f32;
+                    from * (1.0 - t).powi(2) + control * 2.0 * (1.0 - t) * t + to * t.powi(2)
+                }));
+                current = to;
+            }
+            _ => {
+                let (from, control_1, control_2, to) = (current, point(0), point(2), point(4));
+                points.extend((1..=CURVE_SEGMENTS).map(|s| {
+                    let t = s as f32 / CURVE_SEGMENTS as f32;
+                    from * (1.0 - t).powi(3)
+                        + control_1 * 3.0 * (1.0 - t).powi(2) * t
+                        + control_2 * 3.0 * (1.0 - t) * t.powi(2)
+                        + to * t.powi(3)
+                }));
+                current = to;
+            }
+        }
+    }
+
+    Some(points)
+}
+
+enum Token {
+    Command(char),
+    Number(f32),
+}
+
+fn tokenize(path: &str) -> Option<Vec<Token>> {
+    let chars = path.chars().collect::<Vec<char>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch.is_whitespace() || ch == ',' {
+            i += 1;
+        } else if ch.is_ascii_alphabetic() && !ch.eq_ignore_ascii_case(&'e') {
+            tokens.push(Token::Command(ch));
+            i += 1;
+        } else {
+            // a number ends on the second sign, the second dot or anything else
+            let from = i;
+            let mut seen_dot = false;
+            let mut seen_exponent = false;
+            if chars[i] == '-' || chars[i] == '+' {
+                i += 1;
+            }
+            while i < chars.len() {
+                match chars[i] {
+                    '0'..='9' => {}
+                    '.' if !seen_dot && !seen_exponent => seen_dot = true,
+                    'e' | 'E' if !seen_exponent => {
+                        seen_exponent = true;
+                        if i + 1 < chars.len() && (chars[i + 1] == '-' || chars[i + 1] == '+') {
+                            i += 1;
+                        }
+                    }
+                    _ => break,
+                }
+                i += 1;
+            }
+            let number = chars[from..i].iter().collect::<String>().parse().ok()?;
+            tokens.push(Token::Number(number));
+        }
+    }
+    Some(tokens)
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            ShapeSource::Points(points) => write!(f, "{}: {} points", self.name, points.len()),
+            ShapeSource::SvgPath(path) => write!(f, "{}: {}", self.name, path),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::{f32::consts::PI, ops::RangeInclusive};
+
+use nannou::{
+    color::Rgb,
+    geom::{pt2, Point2},
+};
+
+// how many segments the round caps have
+const CAP_SEGMENTS: usize = 8;
+// the miter join is beveled, if it is longer than this times the half width
+const MITER_LIMIT: f32 = 3.0;
+
+// the polyline of a branch with the width on every dot, this is what the renderers draw
+#[derive(Clone)]
+pub struct Stroke {
+    pub points: Vec<Point2>,
+    // the width and the color of the segment, that ends on the dot
+    pub widths: Vec<f32>,
+    pub colors: Vec<Rgb>,
+}
+
+impl Stroke {
+    // the part of the stroke, that is grown by `grown` segments, the last segment is interpolated
+    pub fn grown_part(&self, grown: f32) -> Stroke {
+        let full = (grown.max(0.0).floor() as usize).min(self.points.len() - 1);
+        let mut res = Stroke {
+            points: self.points[..=full].to_vec(),
+            widths: self.widths[..=full].to_vec(),
+            colors: self.colors[..=full].to_vec(),
+        };
+
+        let fraction = grown - full as f32;
+        if fraction > 0.0 && full + 1 < self.points.len() {
+            res.points
+                .push(self.points[full].lerp(self.points[full + 1], fraction));
+            res.widths.push(self.widths[full + 1]);
+            res.colors.push(self.colors[full + 1]);
+        }
+        res
+    }
+
+    // the stroke split in the parts, where all segments have the same color, with their color,
+    // the neighbouring parts share the dot between them
+    pub fn color_runs(&self) -> Vec<(Stroke, Rgb)> {
+        self.color_run_ranges()
+            .into_iter()
+            .map(|(range, color)| {
+                let run = Stroke {
+                    points: self.points[range.clone()].to_vec(),
+                    widths: self.widths[range.clone()].to_vec(),
+                    colors: self.colors[range].to_vec(),
+                };
+                (run, color)
+            })
+            .collect()
+    }
+
+    // the indices of the dots of every part of the same color
+    pub fn color_run_ranges(&self) -> Vec<(RangeInclusive<usize>, Rgb)> {
+        let mut runs = vec![];
+        let mut run_start = 0;
+        for i in 1..self.points.len() {
+            let is_last = i + 1 == self.points.len();
+            if is_last || self.colors[i + 1] != self.colors[i] {
+                runs.push((run_start..=i, self.colors[i]));
+                run_start = i;
+            }
+        }
+        runs
+    }
+
+    // the filled outline of the stroke, the width is interpolated smoothly between the dots.
+    // It goes along the left side, around the round end cap, back along the right side and around
+    // the round start cap, so the branches starting on a fork are joined with a round end
+    pub fn outline(&self) -> Vec<Point2> {
+        self.outline_with_sources()
+            .into_iter()
+            .map(|(point, _)| point)
+            .collect()
+    }
+
+    // the outline with the index of the dot, from which every point of it comes
+    pub fn outline_with_sources(&self) -> Vec<(Point2, usize)> {
+        // the dots on the same place have no direction
+        let mut points: Vec<Point2> = vec![];
+        let mut half_widths = vec![];
+        let mut sources = vec![];
+        for (i, (point, width)) in self.points.iter().zip(self.widths.iter()).enumerate() {
+            if points
+                .last()
+                .is_none_or(|last| last.distance(*point) > f32::EPSILON)
+            {
+                points.push(*point);
+                half_widths.push(width / 2.0);
+                sources.push(i);
+            }
+        }
+        if points.len() < 2 {
+            return vec![];
+        }
+
+        // the left normal of every segment
+        let normals = points
+            .windows(2)
+            .map(|segment| (segment[1] - segment[0]).normalize().perp())
+            .collect::<Vec<Point2>>();
+
+        let last = points.len() - 1;
+        let mut left = vec![];
+        let mut right = vec![];
+        for (i, (point, half_width)) in points.iter().zip(half_widths.iter()).enumerate() {
+            let normal_in = normals[i.max(1) - 1];
+            let normal_out = normals[i.min(last - 1)];
+            let source = sources[i];
+
+            let miter = (normal_in + normal_out).normalize();
+            let miter_length = half_width / miter.dot(normal_in);
+            if miter_length.is_finite() && miter_length <= MITER_LIMIT * half_width {
+                left.push((*point + miter * miter_length, source));
+                right.push((*point - miter * miter_length, source));
+            } else {
+                // too sharp turn, so the join is beveled
+                left.push((*point + normal_in * *half_width, source));
+                left.push((*point + normal_out * *half_width, source));
+                right.push((*point - normal_in * *half_width, source));
+                right.push((*point - normal_out * *half_width, source));
+            }
+        }
+
+        let cap = |i: usize, normal: Point2| {
+            let source = sources[i];
+            round_cap(points[i], normal, half_widths[i])
+                .into_iter()
+                .map(move |point| (point, source))
+        };
+        let mut outline = left;
+        outline.extend(cap(last, normals[last - 1]));
+        outline.extend(right.into_iter().rev());
+        outline.extend(cap(0, -normals[0]));
+        outline
+    }
+}
+
+// the points of the half circle around the center, from the side of the normal clockwise to the
+// opposite side, without the both ends
+fn round_cap(center: Point2, normal: Point2, radius: f32) -> Vec<Point2> {
+    let start_angle = normal.y.atan2(normal.x);
+    (1..CAP_SEGMENTS)
+        .map(|i| {
+            let angle = start_angle - PI * i as f32 / CAP_SEGMENTS as f32;
+            center + pt2(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
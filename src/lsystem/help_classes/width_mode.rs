@@ -0,0 +1,28 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+// how the width of the branches is computed, the line weight is always the width of the twigs
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum WidthMode {
+    // every branch has the line weight
+    Constant,
+    // every lower branch order makes the branch thicker by the factor
+    Order(f32),
+    // the width grows by the factor with the distance to the farthest tip
+    TipDistance(f32),
+    // the width of the parent comes from the widths of its children:
+    // parent^exponent = sum(child^exponent), 2.0 is the rule of Leonardo da Vinci
+    PipeModel(f32),
+}
+
+impl Display for WidthMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WidthMode::Constant => write!(f, "Constant"),
+            WidthMode::Order(factor) => write!(f, "Order({})", factor),
+            WidthMode::TipDistance(factor) => write!(f, "TipDistance({})", factor),
+            WidthMode::PipeModel(exponent) => write!(f, "PipeModel({})", exponent),
+        }
+    }
+}
@@ -1,17 +1,24 @@
 use std::{
     collections::HashMap,
+    f32::consts::PI,
     hash::{Hash, Hasher},
 };
 
 use super::{
-    help_classes::{BranchDot, Rules},
+    help_classes::{
+        BranchDot, Circle, ColorMode, Jitter, Polygon, Rules, ShapeInstance, WidthMode,
+    },
     lsystem_config::LsystemConfig,
     lsystem_tree::LsystemTree,
+    lsystem_tree_3d::LsystemTree3D,
     Behaviour,
 };
+use crate::{constants::MAX_SYMBOLS, misc::hex_to_rgb};
 use nannou::{
-    geom::{pt2, Point2},
-    math::Vec2Rotate,
+    color::Rgb,
+    geom::{pt2, pt3, Point2, Point3},
+    glam::{Quat, Vec3},
+    rand::{rngs::StdRng, SeedableRng},
 };
 
 // class lsystem, that have the start string and a list of rules from Vec<Rule>
@@ -24,12 +31,34 @@ pub struct LsystemBuilder {
     start_direction: Point2,
     // rotation in radian
     rotation_factor: f32,
+    // the symbols with their own rotation and step length
+    symbol_angles: HashMap<char, f32>,
+    symbol_steps: HashMap<char, f32>,
     // the scale factor of the groth_step in distance (1 for constant growing, -0.5 for smaller
     // growing on the end of the plant)
     // this factor will be added to the start_direction by growing of our plant
     scale_delta: f32,
     scale_start: f32,
     scale_min: f32,
+
+    // the heading turns to the tropism vector on every step
+    tropism: Option<Point2>,
+    susceptibility: f32,
+
+    // the width of the twigs and how the thicker branches are computed from it
+    line_weight: f32,
+    width_mode: WidthMode,
+
+    // the color of the branches, the palette is used by the SetColor symbols and the gradients
+    main_color: Rgb,
+    color_mode: ColorMode,
+    palette: Vec<Rgb>,
+
+    // the outlines of the shapes by their names
+    shapes: HashMap<String, Vec<Point2>>,
+
+    // the random deviations of the turns and the steps
+    jitter: Jitter,
 }
 
 struct HashDot {
@@ -50,16 +79,43 @@ impl PartialEq for HashDot {
     }
 }
 
-// help struct for generating lsystem tree
+// help struct for generating lsystem tree, the turtle has the frame of its heading, left and up
+// directions, for the 2D symbols the up stays the z axis
+#[derive(Clone)]
 struct DotData {
-    pos: Point2,
-    dir: Point2,
+    pos: Point3,
+    heading: Vec3,
+    left: Vec3,
+    up: Vec3,
     scale: f32,
+    // the distance from the root along the branches
+    length: f32,
+    // the index in the palette, that was set by the last color symbol
+    color_index: usize,
+    // the factor of the width, that is changed by the width symbols
+    width_scale: f32,
 }
 
 impl DotData {
-    fn new(pos: Point2, dir: Point2, scale: f32) -> DotData {
-        DotData { pos, dir, scale }
+    fn new(pos: Point3, heading: Vec3, scale: f32) -> DotData {
+        DotData {
+            pos,
+            heading,
+            left: Vec3::Z.cross(heading),
+            up: Vec3::Z,
+            scale,
+            length: 0.0,
+            color_index: 0,
+            width_scale: 1.0,
+        }
+    }
+
+    // rotates the whole frame around the axis, counterclockwise looking against the axis
+    fn rotate(&mut self, axis: Vec3, angle: f32) {
+        let rotation = Quat::from_axis_angle(axis.normalize(), angle);
+        self.heading = rotation * self.heading;
+        self.left = rotation * self.left;
+        self.up = rotation * self.up;
     }
 }
 
@@ -70,20 +126,56 @@ impl LsystemBuilder {
             rules: config.rules.clone(),
             start_direction: config.start_direction,
             rotation_factor: config.rotation_factor,
+            symbol_angles: config.symbol_angles.clone(),
+            symbol_steps: config.symbol_steps.clone(),
             scale_delta: config.scale_delta,
             scale_start: config.scale_start,
             scale_min: config.scale_min,
+            tropism: config.tropism,
+            susceptibility: config.susceptibility,
+            line_weight: config.line_weight,
+            width_mode: config.width_mode,
+            main_color: hex_to_rgb(&config.main_color),
+            color_mode: config.color_scheme.mode,
+            palette: config.color_scheme.palette_colors(),
+            shapes: config
+                .shapes
+                .iter()
+                .map(|shape| (shape.name.clone(), shape.outline()))
+                .collect(),
+            jitter: config.jitter,
         }
     }
 
-    // gives a LsystemTree from self with
-    pub fn build_tree(&self, lvl: &usize) -> LsystemTree {
+    // gives a LsystemTree3D from self with, its tree is the 3D tree seen from the front
+    pub fn build_tree_3d(&self, lvl: &usize) -> LsystemTree3D {
         let lsystem = self.generate_sequence(lvl);
-        self.lsystem_to_tree(&lsystem)
+        let mut tree_3d = self.lsystem_to_tree(&lsystem);
+        tree_3d.tree.set_widths(self.width_mode, self.line_weight);
+        tree_3d.tree.set_gradient(self.color_mode, &self.palette);
+        tree_3d
+    }
+
+    // the color of the turtle, only the Symbols mode uses the color index, the gradients are set
+    // when the whole tree is known
+    fn dot_color(&self, color_index: usize) -> Rgb {
+        match self.color_mode {
+            ColorMode::Symbols if !self.palette.is_empty() => {
+                self.palette[color_index % self.palette.len()]
+            }
+            _ => self.main_color,
+        }
     }
 
     // generating new string lsystem to given lvl
     fn generate_sequence(&self, lvl: &usize) -> String {
+        let count = self.rules.symbols_count(&self.axiom, *lvl, MAX_SYMBOLS);
+        if count > MAX_SYMBOLS {
+            panic!(
+                "The tree of deep {} has more than {} symbols",
+                lvl, MAX_SYMBOLS
+            );
+        }
         // sequence of every lvl
         let mut lvl_sequence = self.axiom.clone();
 
@@ -95,7 +187,7 @@ impl LsystemBuilder {
                 temp.push_str(
                     self.rules
                         .get_text(&ch)
-                        .expect(format!("No rule for {}", ch).as_str()),
+                        .unwrap_or_else(|| panic!("No rule for {}", ch)),
                 );
             }
 
@@ -106,15 +198,59 @@ impl LsystemBuilder {
         lvl_sequence
     }
 
-    // encodes the given lsystem string in the 2D points friom startpoint = (0.0,0.0)
-    fn lsystem_to_tree(&self, lsystem: &String) -> LsystemTree {
+    // turns the heading to the tropism vector by the angle susceptibility * |H x T| around H x T,
+    // like in "The Algorithmic Beauty of Plants", so the branches curve more the more they are
+    // across it
+    fn bend_to_tropism(&self, dot: &mut DotData, tropism: Point2) {
+        let axis = dot.heading.cross(tropism.extend(0.0));
+        if axis.length() > f32::EPSILON {
+            dot.rotate(axis, self.susceptibility * axis.length());
+        }
+    }
+
+    // the angle of the turn of the symbol with its random deviation
+    fn turn_angle(&self, ch: char, rng: &mut StdRng) -> f32 {
+        let angle = *self.symbol_angles.get(&ch).unwrap_or(&self.rotation_factor);
+        angle + self.jitter.sample(rng, self.jitter.angle)
+    }
+
+    // the length of the step of the symbol with the scale and its random deviation, the turtle
+    // never goes back
+    fn step(&self, ch: char, rng: &mut StdRng, scale: f32) -> f32 {
+        let length = self
+            .symbol_steps
+            .get(&ch)
+            .cloned()
+            .unwrap_or_else(|| self.start_direction.length());
+        length * scale * (1.0 + self.jitter.sample(rng, self.jitter.length)).max(0.0)
+    }
+
+    // encodes the given lsystem string in the 3D points friom startpoint = (0.0,0.0,0.0)
+    fn lsystem_to_tree(&self, lsystem: &str) -> LsystemTree3D {
         let startpoint = pt2(0.0, 0.0);
-        // todo multiple colors
+        // the length of the step without the scale
+        let step_length = self.start_direction.length();
 
         let mut res = vec![startpoint];
+        let mut res_z = vec![0.0];
+        let mut branches_start_z: HashMap<usize, f32> = HashMap::from([(0, 0.0)]);
         let mut branches: HashMap<usize, Vec<BranchDot>> = HashMap::new();
-
-        let mut dot = DotData::new(startpoint, self.start_direction, self.scale_start);
+        // the fork dot and the order (how many forks are before) of every branch
+        let mut branches_start: HashMap<usize, Point2> = HashMap::from([(0, startpoint)]);
+        let mut branches_order: HashMap<usize, usize> = HashMap::from([(0, 0)]);
+        let mut circles: Vec<Circle> = vec![];
+        let mut polygons: Vec<Polygon> = vec![];
+        // the polygons, that are not closed yet
+        let mut open_polygons: Vec<Polygon> = vec![];
+        let mut shape_instances: Vec<ShapeInstance> = vec![];
+        // the same seed gives the same tree
+        let mut rng = StdRng::seed_from_u64(self.jitter.seed);
+
+        let mut dot = DotData::new(
+            pt3(0.0, 0.0, 0.0),
+            self.start_direction.normalize().extend(0.0),
+            self.scale_start,
+        );
         let mut fork_dots: Vec<DotData> = vec![];
 
         let mut current_dots: Vec<BranchDot> = vec![];
@@ -125,68 +261,131 @@ impl LsystemBuilder {
 
         let mut last_created = 0;
         let mut current_branch_id = 0;
+        // the branch, on whose last drawn dot every branch hangs, the branches without a drawn
+        // dot give it to the branches, that start on them
+        let mut hangs_on: HashMap<usize, usize> = HashMap::new();
 
         // todo think about graph as the tree structure
         let mut res_cutted = vec![startpoint];
         let mut branches_cutted: HashMap<usize, Vec<BranchDot>> = HashMap::new();
         let mut fork_dots_cutted: HashMap<HashDot, Vec<usize>> = HashMap::new();
         let mut current_dots_cutted: Vec<BranchDot> = vec![];
-        let mut queued_branches_cutted: Vec<Vec<BranchDot>> = vec![];
-        let mut queued_branches_id_cutted: Vec<usize> = vec![];
-        let mut last_created_cutted = 0;
-        let mut current_branch_id_cutted = 0;
+        let last_created_cutted = 0;
+        let current_branch_id_cutted = 0;
 
         for ch in lsystem.chars() {
             if let Some(beh) = self.rules.get_behaviour(&ch) {
                 match beh {
                     Behaviour::DrawForward => {
-                        dot.pos += dot.dir * dot.scale;
+                        if let Some(tropism) = self.tropism {
+                            dir_changed = true;
+                            self.bend_to_tropism(&mut dot, tropism);
+                        }
+                        let step = self.step(ch, &mut rng, dot.scale);
+                        dot.pos += dot.heading * step;
+                        dot.length += step;
                         dot.scale = self.scale_min.max(dot.scale + self.scale_delta);
-                        res.push(dot.pos);
+                        res.push(dot.pos.truncate());
+                        res_z.push(dot.pos.z);
 
                         // for branches
                         let branch_dot = BranchDot {
-                            pos: dot.pos,
+                            pos: dot.pos.truncate(),
+                            z: dot.pos.z,
                             connected_branches_id: vec![],
+                            arc_length: dot.length,
+                            // the widths are set, when the whole tree is known
+                            width: 0.0,
+                            width_scale: dot.width_scale,
+                            color: self.dot_color(dot.color_index),
                         };
                         current_dots.push(branch_dot.clone());
                         if dir_changed {
-                            res_cutted.push(dot.pos);
+                            res_cutted.push(dot.pos.truncate());
                             current_dots_cutted.push(branch_dot.clone());
                             dir_changed = false;
                         }
                     }
+                    Behaviour::MoveForward => {
+                        let step = self.step(ch, &mut rng, dot.scale);
+                        dot.pos += dot.heading * step;
+                        dot.length += step;
+                        res.push(dot.pos.truncate());
+                        res_z.push(dot.pos.z);
+                        dir_changed = true;
+
+                        if let Some(last) = current_dots.last_mut() {
+                            // the drawn part is closed and the branch goes on from the new place
+                            // as a new branch, that grows after it
+                            last.connected_branches_id.push(last_created + 1);
+                            branches.insert(current_branch_id, current_dots);
+                            current_dots = vec![];
+                            last_created += 1;
+                            hangs_on.insert(last_created, current_branch_id);
+                            let order = branches_order[&current_branch_id];
+                            branches_order.insert(last_created, order);
+                            current_branch_id = last_created;
+                        }
+                        // nothing is drawn yet, so only the start of the branch is moved
+                        branches_start.insert(current_branch_id, dot.pos.truncate());
+                        branches_start_z.insert(current_branch_id, dot.pos.z);
+                    }
                     Behaviour::RotateLeft => {
                         dir_changed = true;
-                        dot.dir = dot.dir.rotate(self.rotation_factor);
+                        dot.rotate(dot.up, self.turn_angle(ch, &mut rng));
                     }
                     Behaviour::RotateRight => {
                         dir_changed = true;
-                        dot.dir = dot.dir.rotate(-self.rotation_factor);
+                        dot.rotate(dot.up, -self.turn_angle(ch, &mut rng));
+                    }
+                    Behaviour::TurnAround => {
+                        dir_changed = true;
+                        dot.rotate(dot.up, PI);
+                    }
+                    Behaviour::PitchDown => {
+                        dir_changed = true;
+                        dot.rotate(dot.left, self.turn_angle(ch, &mut rng));
+                    }
+                    Behaviour::PitchUp => {
+                        dir_changed = true;
+                        dot.rotate(dot.left, -self.turn_angle(ch, &mut rng));
+                    }
+                    Behaviour::RollLeft => {
+                        dir_changed = true;
+                        dot.rotate(dot.heading, -self.turn_angle(ch, &mut rng));
+                    }
+                    Behaviour::RollRight => {
+                        dir_changed = true;
+                        dot.rotate(dot.heading, self.turn_angle(ch, &mut rng));
                     }
 
                     // on branching push the current dots in the previos branch and start a new uniqe branch
                     Behaviour::Branch => {
-                        fork_dots.push(DotData::new(dot.pos, dot.dir, dot.scale));
-
-                        if current_dots.len() == 0 {
-                            let mut i = queued_branches.len() - 1;
-                            let mut temp = queued_branches.get_mut(i).unwrap();
-                            while temp.len() == 0 {
-                                i -= 1;
-                                temp = queued_branches.get_mut(i).unwrap();
-                            }
-                            temp.last_mut()
-                                .unwrap()
-                                .connected_branches_id
-                                .push(last_created + 1);
+                        fork_dots.push(dot.clone());
+
+                        // without a drawn dot the new branch hangs on the same dot as the
+                        // current one, that is in a queued or a closed branch
+                        let fork_branch = match current_dots.is_empty() {
+                            true => *hangs_on
+                                .get(&current_branch_id)
+                                .expect("There is a [ before the first drawn dot in lsystem"),
+                            false => current_branch_id,
+                        };
+                        let fork_branch_dots = if fork_branch == current_branch_id {
+                            &mut current_dots
+                        } else if let Some(i) =
+                            queued_branches_id.iter().rposition(|id| *id == fork_branch)
+                        {
+                            &mut queued_branches[i]
                         } else {
-                            current_dots
-                                .last_mut()
-                                .unwrap()
-                                .connected_branches_id
-                                .push(last_created + 1);
-                        }
+                            branches.get_mut(&fork_branch).unwrap()
+                        };
+                        fork_branch_dots
+                            .last_mut()
+                            .unwrap()
+                            .connected_branches_id
+                            .push(last_created + 1);
+                        hangs_on.insert(last_created + 1, fork_branch);
 
                         // queue the current branch
                         queued_branches.push(current_dots);
@@ -196,19 +395,21 @@ impl LsystemBuilder {
                         current_dots = vec![];
                         last_created += 1;
                         current_branch_id = last_created;
+                        branches_start.insert(current_branch_id, dot.pos.truncate());
+                        branches_start_z.insert(current_branch_id, dot.pos.z);
+                        branches_order.insert(current_branch_id, fork_dots.len());
 
                         // todo in res_cutted only the dots, that are new branching or new
                         // direction
                         // this is the fork_dots to branches hashmap, at the end change the dots in
                         // the res_cutted so, that they are connected to those branches :)
-                        if let Some(connected_branches) =
-                            fork_dots_cutted.get_mut(&HashDot { pos: dot.pos })
+                        let pos = dot.pos.truncate();
+                        if let Some(connected_branches) = fork_dots_cutted.get_mut(&HashDot { pos })
                         {
                             connected_branches.push(last_created_cutted + 1);
                         } else {
-                            fork_dots_cutted
-                                .insert(HashDot { pos: dot.pos }, vec![last_created_cutted + 1]);
-                            res_cutted.push(dot.pos);
+                            fork_dots_cutted.insert(HashDot { pos }, vec![last_created_cutted + 1]);
+                            res_cutted.push(pos);
                         }
                     }
                     Behaviour::BranchStop => {
@@ -220,19 +421,75 @@ impl LsystemBuilder {
                         current_dots = queued_branches.pop().unwrap();
                         current_branch_id = queued_branches_id.pop().unwrap();
                     }
+                    Behaviour::IncrementWidth(step) => dot.width_scale += step,
+                    Behaviour::DecrementWidth(step) => {
+                        dot.width_scale = (dot.width_scale - step).max(0.0)
+                    }
+                    Behaviour::SetColor(color_index) => dot.color_index = *color_index,
+                    Behaviour::NextColor => dot.color_index += 1,
+                    Behaviour::ScaleStep(factor) => dot.scale *= factor,
+                    Behaviour::DrawDot(radius) => circles.push(Circle {
+                        pos: dot.pos.truncate(),
+                        z: dot.pos.z,
+                        radius: radius * dot.scale,
+                        color: self.dot_color(dot.color_index),
+                        branch_id: current_branch_id,
+                        i_on_branch: current_dots.len(),
+                    }),
+                    Behaviour::StartPolygon => open_polygons.push(Polygon {
+                        points: vec![],
+                        points_z: vec![],
+                        color: self.main_color,
+                        branch_id: current_branch_id,
+                        i_on_branch: current_dots.len(),
+                    }),
+                    Behaviour::PolygonVertex => {
+                        let polygon = open_polygons
+                            .last_mut()
+                            .expect("There is a . outside of { } in lsystem");
+                        polygon.points.push(dot.pos.truncate());
+                        polygon.points_z.push(dot.pos.z);
+                    }
+                    Behaviour::EndPolygon => {
+                        let mut polygon =
+                            open_polygons.pop().expect("There are to many } in lsystem");
+                        // the polygon has the color of the turtle on its end
+                        polygon.color = self.dot_color(dot.color_index);
+                        if polygon.points.len() >= 3 {
+                            polygons.push(polygon);
+                        }
+                    }
+                    Behaviour::StampShape(name) => {
+                        if !self.shapes.contains_key(name) {
+                            panic!("There is no shape named {}", name);
+                        }
+                        shape_instances.push(ShapeInstance {
+                            shape: name.clone(),
+                            pos: dot.pos.truncate(),
+                            z: dot.pos.z,
+                            right: -dot.left,
+                            heading: dot.heading,
+                            rotation: ShapeInstance::rotation_of(dot.heading.truncate(), 0.0),
+                            scale: step_length * dot.scale,
+                            color: self.dot_color(dot.color_index),
+                            branch_id: current_branch_id,
+                            i_on_branch: current_dots.len(),
+                        });
+                    }
+                    Behaviour::NoOp => {}
                 }
             } else {
-                unimplemented!("The meaning of the {ch} char is not implemented");
+                panic!("No rule for {}", ch);
             }
         }
         branches.insert(current_branch_id, current_dots);
         branches_cutted.insert(current_branch_id_cutted, current_dots_cutted);
 
-        if let Some(beh) = self.rules.get_behaviour(&lsystem.chars().last().unwrap()) {
-            match beh {
-                Behaviour::DrawForward => res.push(dot.pos),
-                _ => {}
-            }
+        if let Some(Behaviour::DrawForward) =
+            self.rules.get_behaviour(&lsystem.chars().last().unwrap())
+        {
+            res.push(dot.pos.truncate());
+            res_z.push(dot.pos.z);
         }
 
         // adding the connected branch to cutted_dots
@@ -240,24 +497,176 @@ impl LsystemBuilder {
             .iter_mut()
             .map(|dot| {
                 if let Some(branches) = fork_dots_cutted.get(&HashDot { pos: *dot }) {
-                    return BranchDot {
+                    BranchDot {
                         pos: *dot,
+                        // the z, length, width and color are not tracked for the cutted dots
+                        z: 0.0,
                         connected_branches_id: branches.clone(),
-                    };
+                        arc_length: 0.0,
+                        width: 0.0,
+                        width_scale: 1.0,
+                        color: self.main_color,
+                    }
                 } else {
-                    return BranchDot {
+                    BranchDot {
                         pos: *dot,
+                        z: 0.0,
                         connected_branches_id: vec![],
-                    };
+                        arc_length: 0.0,
+                        width: 0.0,
+                        width_scale: 1.0,
+                        color: self.main_color,
+                    }
                 }
             })
             .collect::<Vec<BranchDot>>();
 
-        LsystemTree {
-            dots: res,
-            dots_cutted: res_cutted,
-            branches,
-            branches_cutted,
+        LsystemTree3D {
+            tree: LsystemTree {
+                dots: res,
+                dots_cutted: res_cutted,
+                branches,
+                branches_cutted,
+                branches_start,
+                branches_order,
+                circles,
+                polygons,
+                shapes: self.shapes.clone(),
+                shape_instances,
+            },
+            dots_z: res_z,
+            branches_start_z,
+            symbols_count: lsystem.chars().count(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::config::AppConfig;
+
+    // the tree of the axiom, the turns are right angles and the steps have the length 1
+    fn build(axiom: &str) -> LsystemTree3D {
+        let mut app_config = AppConfig::new(vec![0]);
+        app_config.config.axiom = axiom.to_string();
+        app_config.config.start_direction = pt2(0.0, 1.0);
+        app_config.config.rotation_factor = FRAC_PI_2;
+        app_config.config.symbol_angles.clear();
+        app_config.config.symbol_steps.clear();
+        app_config.config.tropism = None;
+        app_config.config.scale_delta = 0.0;
+        app_config.config.scale_start = 1.0;
+        app_config.config.jitter.angle = 0.0;
+        app_config.config.jitter.length = 0.0;
+        LsystemBuilder::new(&app_config.config).build_tree_3d(&0)
+    }
+
+    // the 3D points of the main branch
+    fn main_branch(axiom: &str) -> Vec<Point3> {
+        build(axiom).tree.branches[&0]
+            .iter()
+            .map(|dot| dot.pos.extend(dot.z))
+            .collect()
+    }
+
+    fn assert_points(axiom: &str, expected: &[(f32, f32, f32)]) {
+        let points = main_branch(axiom);
+        assert_eq!(points.len(), expected.len(), "{}", axiom);
+        for (point, (x, y, z)) in points.iter().zip(expected.iter()) {
+            assert!(
+                point.distance(pt3(*x, *y, *z)) < 1e-4,
+                "{}: {:?}",
+                axiom,
+                points
+            );
+        }
+    }
+
+    #[test]
+    fn turns_stay_in_the_plane() {
+        assert_points(
+            "F+F-F",
+            &[(0.0, 1.0, 0.0), (-1.0, 1.0, 0.0), (-1.0, 2.0, 0.0)],
+        );
+        assert_points("F|F", &[(0.0, 1.0, 0.0), (0.0, 0.0, 0.0)]);
+    }
+
+    #[test]
+    fn pitch_goes_out_of_the_plane_and_back() {
+        let pitched = main_branch("&F");
+        assert!(pitched[0].truncate().length() < 1e-4);
+        assert!((pitched[0].z.abs() - 1.0).abs() < 1e-4);
+        assert_points("&^F", &[(0.0, 1.0, 0.0)]);
+        // the pitch down and up go to the opposite sides
+        assert!((main_branch("^F")[0].z + pitched[0].z).abs() < 1e-4);
+    }
+
+    #[test]
+    fn roll_turns_the_left_and_up_of_the_turtle() {
+        // after the half roll the left turn goes to the right
+        assert_points("//+F", &[(1.0, 0.0, 0.0)]);
+        assert_points("\\\\+F", &[(1.0, 0.0, 0.0)]);
+        // after the quarter roll the left turn goes out of the plane like the pitch
+        let rolled = main_branch("/+F");
+        assert!(rolled[0].truncate().length() < 1e-4);
+        assert!((rolled[0].z.abs() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn frame_stays_orthonormal() {
+        let mut dot = DotData::new(pt3(0.0, 0.0, 0.0), Vec3::Y, 1.0);
+        for i in 0..1000 {
+            let axis = [dot.heading, dot.left, dot.up][i % 3];
+            dot.rotate(axis, 0.1 + i as f32 * 0.37);
+        }
+        for (a, b) in [
+            (dot.heading, dot.left),
+            (dot.left, dot.up),
+            (dot.up, dot.heading),
+        ] {
+            assert!(a.dot(b).abs() < 1e-3);
+        }
+        for axis in [dot.heading, dot.left, dot.up] {
+            assert!((axis.length() - 1.0).abs() < 1e-3);
+        }
+        // the frame is right handed
+        assert!(dot.heading.cross(dot.left).distance(dot.up) < 1e-3);
+    }
+
+    #[test]
+    fn symbols_are_counted_without_the_sequence() {
+        let config = AppConfig::new(vec![0]).config;
+        let builder = LsystemBuilder::new(&config);
+        for deep in 0..5 {
+            assert_eq!(
+                config.rules.symbols_count(&config.axiom, deep, usize::MAX),
+                builder.generate_sequence(&deep).chars().count()
+            );
+        }
+        // the counting stops above the max
+        let count = config.rules.symbols_count(&config.axiom, 1000, MAX_SYMBOLS);
+        assert!(count > MAX_SYMBOLS && count < usize::MAX);
+        assert!(!config.fits(1000));
+        assert!(config.fits(5));
+    }
+
+    #[test]
+    fn branch_after_a_move_forks_from_the_last_drawn_dot() {
+        let tree = build("Ff[F]").tree;
+        // the moved part and the branch start on the dot before the move
+        assert_eq!(tree.branches[&0].len(), 1);
+        assert_eq!(tree.branches[&0][0].connected_branches_id, vec![1, 2]);
+        assert!(tree.branches_start[&2].distance(pt2(0.0, 2.0)) < 1e-4);
+        assert!(tree.branches[&2][0].pos.distance(pt2(0.0, 3.0)) < 1e-4);
+
+        // in a branch the fork is on its own drawn dot, not on the dot of its parent
+        let tree = build("F[Ff[F]]").tree;
+        assert_eq!(tree.branches[&0][0].connected_branches_id, vec![1]);
+        assert_eq!(tree.branches[&1].len(), 1);
+        assert_eq!(tree.branches[&1][0].connected_branches_id, vec![2, 3]);
+        assert!(tree.branches_start[&3].distance(pt2(0.0, 3.0)) < 1e-4);
+    }
+}
@@ -1,6 +1,7 @@
-use super::help_classes::Rules;
+use super::help_classes::{ColorScheme, Jitter, Rules, Shape, WidthMode};
+use crate::constants::MAX_SYMBOLS;
 use nannou::geom::Point2;
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
 #[derive(Clone)]
 pub struct LsystemConfig {
@@ -10,10 +11,14 @@ pub struct LsystemConfig {
 
     // for LsystemBuilder
     pub main_color: String,
+    pub color_scheme: ColorScheme,
     // the step with which the dot jumps further
     pub start_direction: Point2,
     // rotation in radian
     pub rotation_factor: f32,
+    // the rotation in radian and the step length of the symbols, that don't use the ones above
+    pub symbol_angles: HashMap<char, f32>,
+    pub symbol_steps: HashMap<char, f32>,
     // the scale factor of the groth_step in distance (1 for constant growing, -0.5 for smaller
     // growing on the end of the plant)
 
@@ -22,8 +27,28 @@ pub struct LsystemConfig {
     pub scale_start: f32,
     pub scale_min: f32,
 
+    // the width of the twigs, the thicker branches are computed from it by the width mode
     pub line_weight: f32,
+    pub width_mode: WidthMode,
     pub wind_power: Option<Point2>,
+
+    // the direction, to which the branches bend on every step (gravity or light), and how
+    // strong they follow it
+    pub tropism: Option<Point2>,
+    pub susceptibility: f32,
+
+    // the shapes, that the StampShape symbols stamp by their names
+    pub shapes: Vec<Shape>,
+
+    // the random deviations of the turns and the steps
+    pub jitter: Jitter,
+}
+
+impl LsystemConfig {
+    // false, if the tree of the deep would have more than MAX_SYMBOLS symbols
+    pub fn fits(&self, deep: usize) -> bool {
+        self.rules.symbols_count(&self.axiom, deep, MAX_SYMBOLS) <= MAX_SYMBOLS
+    }
 }
 
 impl Display for LsystemConfig {
@@ -33,6 +58,31 @@ impl Display for LsystemConfig {
             f,
             "Main color:{}\nStart direction:{}\nRotation factor:{}\nScale factor:{}\nMin scale factor:{}",
             self.main_color, self.start_direction, self.rotation_factor, self.scale_delta, self.scale_min)?;
+        for (ch, angle) in self.symbol_angles.iter() {
+            writeln!(f, "Rotation of {}:{}", ch, angle)?;
+        }
+        for (ch, step) in self.symbol_steps.iter() {
+            writeln!(f, "Step of {}:{}", ch, step)?;
+        }
+        writeln!(f, "Color scheme:{}", self.color_scheme)?;
+        writeln!(
+            f,
+            "Line weight:{} Width mode:{}",
+            self.line_weight, self.width_mode
+        )?;
+        if self.jitter.is_enabled() {
+            writeln!(f, "Jitter {}", self.jitter)?;
+        }
+        for shape in self.shapes.iter() {
+            writeln!(f, "Shape {}", shape)?;
+        }
+        if let Some(tropism) = self.tropism {
+            writeln!(
+                f,
+                "Tropism:{} Susceptibility:{}",
+                tropism, self.susceptibility
+            )?;
+        }
         Ok(())
     }
 }
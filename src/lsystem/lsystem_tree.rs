@@ -1,5 +1,10 @@
-use super::help_classes::BranchDot;
-use nannou::geom::{pt2, Point2};
+use super::help_classes::{
+    gradient, BranchDot, Circle, ColorMode, Polygon, ShapeInstance, Stroke, WidthMode,
+};
+use nannou::{
+    color::Rgb,
+    geom::{pt2, Point2},
+};
 use std::collections::HashMap;
 
 // This structure is used to store and changing the builded LsystemTree
@@ -9,6 +14,18 @@ pub struct LsystemTree {
     pub dots_cutted: Vec<BranchDot>,
     pub branches: HashMap<usize, Vec<BranchDot>>,
     pub branches_cutted: HashMap<usize, Vec<BranchDot>>,
+    // the dot where the branch forks from its parent (the start point for the main branch)
+    pub branches_start: HashMap<usize, Point2>,
+    // how deep the branch is nested, 0 for the main branch
+    pub branches_order: HashMap<usize, usize>,
+    // the circles of the DrawDot symbols
+    pub circles: Vec<Circle>,
+    // the polygons of the { . } symbols
+    pub polygons: Vec<Polygon>,
+    // the outlines of the shapes by their names, once for all their instances
+    pub shapes: HashMap<String, Vec<Point2>>,
+    // the shapes of the StampShape symbols
+    pub shape_instances: Vec<ShapeInstance>,
 }
 
 impl LsystemTree {
@@ -32,5 +49,421 @@ impl LsystemTree {
         for dot in self.dots_cutted.iter_mut() {
             dot.pos = pt2(to_point.x + dot.pos.x, to_point.y + dot.pos.y);
         }
+
+        for (_, start) in self.branches_start.iter_mut() {
+            *start += to_point;
+        }
+
+        for circle in self.circles.iter_mut() {
+            circle.pos += to_point;
+        }
+
+        for polygon in self.polygons.iter_mut() {
+            for point in polygon.points.iter_mut() {
+                *point += to_point;
+            }
+        }
+
+        for instance in self.shape_instances.iter_mut() {
+            instance.pos += to_point;
+        }
+    }
+
+    // the outline of the instance on its place in the tree
+    pub fn shape_outline(&self, instance: &ShapeInstance) -> Vec<Point2> {
+        self.shapes
+            .get(&instance.shape)
+            .map(|outline| {
+                outline
+                    .iter()
+                    .map(|point| instance.transform(*point))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // the ids of all branches, sorted so that the output of the exporters is stable
+    pub fn branch_ids(&self) -> Vec<usize> {
+        let mut ids = self.branches.keys().cloned().collect::<Vec<usize>>();
+        ids.sort();
+        ids
+    }
+
+    // moves every dot of the tree to the dot of the rest tree plus its offset, the rest tree is
+    // the same tree before the bending, so the tree can be bended in every frame without
+    // building it again
+    pub fn bend_from(&mut self, rest: &LsystemTree, offset: impl Fn(&BranchDot) -> Point2) {
+        for (id, branch) in self.branches.iter_mut() {
+            if let Some(rest_branch) = rest.branches.get(id) {
+                for (dot, rest_dot) in branch.iter_mut().zip(rest_branch.iter()) {
+                    dot.pos = rest_dot.pos + offset(rest_dot);
+                }
+            }
+        }
+
+        // the fork dot is bended like the dot of the parent branch on the same place
+        for (id, start) in self.branches_start.iter_mut() {
+            if let Some(fork) = rest.fork_dot(id) {
+                *start = fork.pos + offset(&fork);
+            }
+        }
+
+        // the circles and the polygons are bended like the dot of their branch, on which they
+        // were drawn
+        for (circle, rest_circle) in self.circles.iter_mut().zip(rest.circles.iter()) {
+            if let Some(dot) = rest.dot_before(rest_circle.branch_id, rest_circle.i_on_branch) {
+                circle.pos = rest_circle.pos + offset(dot);
+            }
+        }
+        for (polygon, rest_polygon) in self.polygons.iter_mut().zip(rest.polygons.iter()) {
+            if let Some(dot) = rest.dot_before(rest_polygon.branch_id, rest_polygon.i_on_branch) {
+                let offset = offset(dot);
+                for (point, rest_point) in polygon.points.iter_mut().zip(rest_polygon.points.iter())
+                {
+                    *point = *rest_point + offset;
+                }
+            }
+        }
+        for (instance, rest_instance) in self
+            .shape_instances
+            .iter_mut()
+            .zip(rest.shape_instances.iter())
+        {
+            if let Some(dot) = rest.dot_before(rest_instance.branch_id, rest_instance.i_on_branch) {
+                instance.pos = rest_instance.pos + offset(dot);
+            }
+        }
+    }
+
+    // the last dot of the branch, that was drawn before the i-th dot
+    fn dot_before(&self, branch_id: usize, i_on_branch: usize) -> Option<&BranchDot> {
+        let branch = self.branches.get(&branch_id)?;
+        branch.get(i_on_branch.max(1) - 1)
+    }
+
+    // the fork dot of the branch as the dot before its first dot, with the arc length of the fork
+    pub fn fork_dot(&self, id: &usize) -> Option<BranchDot> {
+        let start = self.branches_start.get(id)?;
+        let first = self.branches.get(id)?.first()?;
+        Some(BranchDot {
+            pos: *start,
+            connected_branches_id: vec![],
+            arc_length: first.arc_length - first.pos.distance(*start),
+            ..first.clone()
+        })
+    }
+
+    // the whole polyline of the branch with the widths and colors, starting in the fork dot, so
+    // that it is connected to the parent branch, the fork dot is like the first dot
+    pub fn branch_stroke(&self, id: &usize) -> Stroke {
+        let mut stroke = Stroke {
+            points: vec![],
+            widths: vec![],
+            colors: vec![],
+        };
+        if let Some(branch) = self.branches.get(id) {
+            if let (Some(start), Some(first)) = (self.branches_start.get(id), branch.first()) {
+                stroke.points.push(*start);
+                stroke.widths.push(first.width);
+                stroke.colors.push(first.color);
+            }
+            stroke.points.extend(branch.iter().map(|dot| dot.pos));
+            stroke.widths.extend(branch.iter().map(|dot| dot.width));
+            stroke.colors.extend(branch.iter().map(|dot| dot.color));
+        }
+        stroke
+    }
+
+    // sets the width of every dot, the line weight is the width of the twigs
+    pub fn set_widths(&mut self, mode: WidthMode, line_weight: f32) {
+        let max_order = self.branches_order.values().max().cloned().unwrap_or(0);
+        // the value of every branch on its first dot, that the parent branch needs
+        let mut base_values: HashMap<usize, f32> = HashMap::new();
+
+        // the children are created after their parent, so with the bigger ids they are
+        // computed before it
+        let mut ids = self.branch_ids();
+        ids.reverse();
+        for id in ids {
+            let order = self.branch_order(&id);
+            let branch = self.branches.get_mut(&id).unwrap();
+            if branch.is_empty() {
+                base_values.insert(id, 0.0);
+                continue;
+            }
+
+            match mode {
+                WidthMode::Constant => branch.iter_mut().for_each(|dot| dot.width = line_weight),
+                WidthMode::Order(factor) => {
+                    let width = line_weight * factor.powi((max_order - order) as i32);
+                    branch.iter_mut().for_each(|dot| dot.width = width);
+                }
+                WidthMode::TipDistance(factor) => {
+                    // the arc length of the farthest tip behind the dot
+                    let mut reach = branch.last().unwrap().arc_length;
+                    for dot in branch.iter_mut().rev() {
+                        for child in dot.connected_branches_id.iter() {
+                            reach = reach.max(*base_values.get(child).unwrap_or(&0.0));
+                        }
+                        dot.width = line_weight + factor * (reach - dot.arc_length);
+                    }
+                    base_values.insert(id, reach);
+                }
+                WidthMode::PipeModel(exponent) => {
+                    // the sum of width^exponent of all twigs behind the dot
+                    let mut flow = line_weight.powf(exponent);
+                    for dot in branch.iter_mut().rev() {
+                        for child in dot.connected_branches_id.iter() {
+                            flow += *base_values.get(child).unwrap_or(&0.0);
+                        }
+                        dot.width = flow.powf(1.0 / exponent);
+                    }
+                    base_values.insert(id, flow);
+                }
+            }
+
+            branch
+                .iter_mut()
+                .for_each(|dot| dot.width *= dot.width_scale);
+        }
+    }
+
+    // colors every dot by its place on the gradient through the colors, the other modes keep the
+    // colors from the building
+    pub fn set_gradient(&mut self, mode: ColorMode, colors: &[Rgb]) {
+        // the gradient value of every dot in the order of the branch dots
+        let mut values: HashMap<usize, Vec<f32>> = HashMap::new();
+        match mode {
+            ColorMode::Single | ColorMode::Symbols => return,
+            ColorMode::Order => {
+                for (id, branch) in self.branches.iter() {
+                    let order = self.branch_order(id) as f32;
+                    values.insert(*id, vec![order; branch.len()]);
+                }
+            }
+            ColorMode::ArcLength => {
+                for (id, branch) in self.branches.iter() {
+                    values.insert(*id, branch.iter().map(|dot| dot.arc_length).collect());
+                }
+            }
+            ColorMode::Depth => {
+                // the count of steps from the root, the parents have the smaller ids, so the
+                // depth of the fork is known before the branch
+                let mut fork_depths: HashMap<usize, usize> = HashMap::new();
+                for id in self.branch_ids() {
+                    let fork_depth = *fork_depths.get(&id).unwrap_or(&0);
+                    let branch = &self.branches[&id];
+                    for (i, dot) in branch.iter().enumerate() {
+                        for child in dot.connected_branches_id.iter() {
+                            fork_depths.insert(*child, fork_depth + i + 1);
+                        }
+                    }
+                    values.insert(
+                        id,
+                        (0..branch.len())
+                            .map(|i| (fork_depth + i + 1) as f32)
+                            .collect(),
+                    );
+                }
+            }
+        }
+
+        let max = values
+            .values()
+            .flatten()
+            .cloned()
+            .fold(0.0, f32::max)
+            .max(f32::EPSILON);
+        for (id, branch) in self.branches.iter_mut() {
+            for (dot, value) in branch.iter_mut().zip(values[id].iter()) {
+                dot.color = gradient(colors, value / max);
+            }
+        }
+
+        // the circles, the polygons and the shapes have the color of the branch on their place
+        let circle_colors = self
+            .circles
+            .iter()
+            .map(|circle| self.dot_before(circle.branch_id, circle.i_on_branch))
+            .map(|dot| dot.map(|dot| dot.color))
+            .collect::<Vec<_>>();
+        for (circle, color) in self.circles.iter_mut().zip(circle_colors) {
+            if let Some(color) = color {
+                circle.color = color;
+            }
+        }
+        let polygon_colors = self
+            .polygons
+            .iter()
+            .map(|polygon| self.dot_before(polygon.branch_id, polygon.i_on_branch))
+            .map(|dot| dot.map(|dot| dot.color))
+            .collect::<Vec<_>>();
+        for (polygon, color) in self.polygons.iter_mut().zip(polygon_colors) {
+            if let Some(color) = color {
+                polygon.color = color;
+            }
+        }
+        let instance_colors = self
+            .shape_instances
+            .iter()
+            .map(|instance| self.dot_before(instance.branch_id, instance.i_on_branch))
+            .map(|dot| dot.map(|dot| dot.color))
+            .collect::<Vec<_>>();
+        for (instance, color) in self.shape_instances.iter_mut().zip(instance_colors) {
+            if let Some(color) = color {
+                instance.color = color;
+            }
+        }
+    }
+
+    // mixes the colors of the far dots with the color, the farthest ones by the strength, the z of
+    // the dots is the depth
+    pub fn fade_by_depth(&mut self, to: Rgb, strength: f32) {
+        let (mut min_z, mut max_z) = (f32::MAX, f32::MIN);
+        for dot in self.branches.values().flatten() {
+            min_z = min_z.min(dot.z);
+            max_z = max_z.max(dot.z);
+        }
+        if max_z - min_z <= f32::EPSILON {
+            return;
+        }
+
+        let fade = |color: Rgb, z: f32| {
+            let t = (max_z - z) / (max_z - min_z) * strength;
+            gradient(&[color, to], t)
+        };
+        for dot in self.branches.values_mut().flatten() {
+            dot.color = fade(dot.color, dot.z);
+        }
+        for circle in self.circles.iter_mut() {
+            circle.color = fade(circle.color, circle.z);
+        }
+        for polygon in self.polygons.iter_mut() {
+            polygon.color = fade(polygon.color, polygon.depth());
+        }
+        for instance in self.shape_instances.iter_mut() {
+            instance.color = fade(instance.color, instance.z);
+        }
+    }
+
+    // multiplies the width of every dot, so the branches can keep their width on the screen
+    pub fn scale_widths(&mut self, factor: f32) {
+        for dot in self.branches.values_mut().flatten() {
+            dot.width *= factor;
+        }
+    }
+
+    // the mean depth of the branch dots, to draw the far branches first
+    pub fn branch_depth(&self, id: &usize) -> f32 {
+        match self.branches.get(id) {
+            Some(branch) if !branch.is_empty() => {
+                branch.iter().map(|dot| dot.z).sum::<f32>() / branch.len() as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn branch_order(&self, id: &usize) -> usize {
+        *self.branches_order.get(id).unwrap_or(&0)
+    }
+
+    // the lower left and the upper right corner of the tree
+    pub fn bounds(&self) -> (Point2, Point2) {
+        let mut min = pt2(f32::MAX, f32::MAX);
+        let mut max = pt2(f32::MIN, f32::MIN);
+        for dot in self.dots.iter() {
+            min = min.min(*dot);
+            max = max.max(*dot);
+        }
+        for circle in self.circles.iter() {
+            min = min.min(circle.pos - circle.radius);
+            max = max.max(circle.pos + circle.radius);
+        }
+        for point in self
+            .polygons
+            .iter()
+            .flat_map(|polygon| polygon.points.iter())
+        {
+            min = min.min(*point);
+            max = max.max(*point);
+        }
+        for instance in self.shape_instances.iter() {
+            for point in self.shape_outline(instance) {
+                min = min.min(point);
+                max = max.max(point);
+            }
+        }
+        (min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::AppConfig, lsystem::LsystemBuilder};
+
+    // a trunk with two twigs on its first dot, the widths are set again by the tests
+    fn fork_tree() -> LsystemTree {
+        let mut app_config = AppConfig::new(vec![0]);
+        app_config.config.axiom = "F[+F][-FF]F".to_string();
+        LsystemBuilder::new(&app_config.config)
+            .build_tree_3d(&0)
+            .tree
+    }
+
+    fn widths(tree: &LsystemTree, id: usize) -> Vec<f32> {
+        tree.branches[&id].iter().map(|dot| dot.width).collect()
+    }
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn pipe_model_sums_the_children() {
+        let mut tree = fork_tree();
+        tree.set_widths(WidthMode::PipeModel(2.0), 2.0);
+        // the trunk below the fork carries the both twigs and its own tip
+        assert_close(&widths(&tree, 0), &[(3.0f32 * 4.0).sqrt(), 2.0]);
+        for id in tree.branch_ids().into_iter().filter(|id| *id != 0) {
+            assert!(widths(&tree, id)
+                .iter()
+                .all(|width| (width - 2.0).abs() < 1e-4));
+        }
+
+        // with the exponent 1 the widths are just added
+        tree.set_widths(WidthMode::PipeModel(1.0), 2.0);
+        assert_close(&widths(&tree, 0), &[6.0, 2.0]);
+    }
+
+    #[test]
+    fn pipe_model_goes_through_the_nested_branches() {
+        let mut app_config = AppConfig::new(vec![0]);
+        app_config.config.axiom = "F[+F[+F]F]F".to_string();
+        let mut tree = LsystemBuilder::new(&app_config.config)
+            .build_tree_3d(&0)
+            .tree;
+        tree.set_widths(WidthMode::PipeModel(1.0), 1.0);
+        // the twig on the twig makes the side branch 2 and the trunk 3 wide
+        assert_close(&widths(&tree, 0), &[3.0, 1.0]);
+        let side = tree.branches[&0][0].connected_branches_id[0];
+        assert_close(&widths(&tree, side), &[2.0, 1.0]);
+    }
+
+    #[test]
+    fn tip_distance_and_order_widths() {
+        let mut tree = fork_tree();
+        let step = tree.branches[&0][0].arc_length;
+        tree.set_widths(WidthMode::TipDistance(0.5), 1.0);
+        // the farthest tip behind the first dot is the end of the longer twig, 2 steps away
+        assert_close(&widths(&tree, 0), &[1.0 + step, 1.0]);
+
+        tree.set_widths(WidthMode::Order(2.0), 1.0);
+        assert_close(&widths(&tree, 0), &[2.0, 2.0]);
+        tree.set_widths(WidthMode::Constant, 1.5);
+        assert_close(&widths(&tree, 0), &[1.5, 1.5]);
     }
 }
@@ -0,0 +1,84 @@
+use super::{help_classes::ShapeInstance, lsystem_tree::LsystemTree};
+use nannou::geom::{Point2, Point3};
+use std::collections::HashMap;
+
+// the tree of the 3D turtle, that is the 2D tree with the x and y of every dot (the view from the
+// front) and the z besides, the branch dots, the circles and the shapes have their z in
+// themselves, so the 2D tree is the special case with every z = 0
+#[derive(Clone)]
+pub struct LsystemTree3D {
+    pub tree: LsystemTree,
+    pub dots_z: Vec<f32>,
+    pub branches_start_z: HashMap<usize, f32>,
+    // the length of the generated lsystem string
+    pub symbols_count: usize,
+}
+
+impl LsystemTree3D {
+    // the trees are placed next to each other in the xy plane
+    pub fn move_tree(&mut self, to_point: Point2) {
+        self.tree.move_tree(to_point);
+    }
+
+    // the tree with every dot projected on the screen, the projection gives the screen position in
+    // x and y and the depth in z, that stays in the z of the dots
+    pub fn project(&self, projection: impl Fn(Point3) -> Point3) -> LsystemTree {
+        let project_dot = |pos: Point2, z: f32| {
+            let projected = projection(pos.extend(z));
+            (projected.truncate(), projected.z)
+        };
+
+        let mut tree = self.tree.clone();
+        for (dot, z) in tree.dots.iter_mut().zip(self.dots_z.iter()) {
+            *dot = project_dot(*dot, *z).0;
+        }
+        for branch in tree.branches.values_mut() {
+            for dot in branch.iter_mut() {
+                (dot.pos, dot.z) = project_dot(dot.pos, dot.z);
+            }
+        }
+        for (id, start) in tree.branches_start.iter_mut() {
+            *start = project_dot(*start, *self.branches_start_z.get(id).unwrap_or(&0.0)).0;
+        }
+        for circle in tree.circles.iter_mut() {
+            (circle.pos, circle.z) = project_dot(circle.pos, circle.z);
+        }
+        for polygon in tree.polygons.iter_mut() {
+            for (point, z) in polygon.points.iter_mut().zip(polygon.points_z.iter_mut()) {
+                (*point, *z) = project_dot(*point, *z);
+            }
+        }
+        // the shapes keep their outline and size, only their place and the direction of the
+        // heading on the screen change
+        for instance in tree.shape_instances.iter_mut() {
+            let tip = projection(instance.pos.extend(instance.z) + instance.heading);
+            (instance.pos, instance.z) = project_dot(instance.pos, instance.z);
+            instance.rotation =
+                ShapeInstance::rotation_of(tip.truncate() - instance.pos, instance.rotation);
+        }
+
+        // the cutted dots have no z, so they are projected as they lie in the xy plane
+        for dot in tree.dots_cutted.iter_mut() {
+            dot.pos = project_dot(dot.pos, 0.0).0;
+        }
+        for branch in tree.branches_cutted.values_mut() {
+            for dot in branch.iter_mut() {
+                dot.pos = project_dot(dot.pos, 0.0).0;
+            }
+        }
+        tree
+    }
+
+    // the 3D points of the branch, like the points of its stroke starting in the fork dot
+    pub fn branch_points(&self, id: &usize) -> Vec<Point3> {
+        let mut points = vec![];
+        if let Some(branch) = self.tree.branches.get(id) {
+            if let (Some(start), false) = (self.tree.branches_start.get(id), branch.is_empty()) {
+                let z = *self.branches_start_z.get(id).unwrap_or(&0.0);
+                points.push(start.extend(z));
+            }
+            points.extend(branch.iter().map(|dot| dot.pos.extend(dot.z)));
+        }
+        points
+    }
+}
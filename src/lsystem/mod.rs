@@ -2,9 +2,14 @@ mod help_classes;
 mod lsystem_builder;
 mod lsystem_config;
 mod lsystem_tree;
+mod lsystem_tree_3d;
 
 // todo make the config with json
-pub use help_classes::{Behaviour, Rule, Rules};
+pub use help_classes::{
+    Behaviour, BranchDot, Circle, ColorMode, ColorScheme, Distribution, Jitter, Polygon, Rule,
+    Rules, Shape, ShapeInstance, ShapeSource, Stroke, WidthMode,
+};
 pub use lsystem_builder::LsystemBuilder;
 pub use lsystem_config::LsystemConfig;
 pub use lsystem_tree::LsystemTree;
+pub use lsystem_tree_3d::LsystemTree3D;
@@ -1,60 +1,138 @@
+mod animation;
+mod camera;
+mod cli;
 mod config;
+mod config_file;
 mod constants;
+mod export;
+mod grown_mesh;
 mod lsystem;
 mod misc;
+mod pan_zoom;
+mod panel;
+mod playback;
+mod rebuild;
+mod wind;
 
-use std::collections::VecDeque;
+use animation::TreeAnimation;
+use camera::{OrbitCamera, Projection};
+use cli::Cli;
+use config::{AppConfig, Planted};
+use config_file::ConfigWatcher;
+use constants::{ANIMATE, DEEPS, MAX_ANIMATED_BRANCHES, PRESETS};
+use grown_mesh::{Anchor, DepthLayers, GrownMesh, ShapeMesh, DEPTH_LAYERS};
+use lsystem::{Circle, LsystemBuilder, LsystemTree, LsystemTree3D, Polygon, ShapeInstance, Stroke};
+use misc::{debug_info, error_overlay, hex_to_rgb, status_overlay, timestamp};
+use nannou::prelude::*;
+use pan_zoom::PanZoom;
+use panel::Panel;
+use playback::Playback;
+use rebuild::{build_catching, Built, Rebuilder};
+use std::{collections::HashMap, path::PathBuf};
+use wind::Wind;
 
-use config::AppConfig;
-use constants::ANIMATE;
-use lsystem::{LsystemBuilder, LsystemTree};
-use misc::hex_to_rgb;
-use nannou::{draw::primitive::Texture, prelude::*};
+// how far the mouse can move between the press and the release of a click, that plants a tree
+const CLICK_DISTANCE: f32 = 3.0;
+// how many seconds the message of the saved file is shown
+const STATUS_SECONDS: f32 = 3.0;
 
 fn main() {
-    nannou::app(model).update(update).simple_window(view).run();
-}
-
-#[derive(Debug, Hash, Eq, PartialEq, Clone)]
-struct BranchInfo {
-    id: usize,
-    i_on_start: usize,
-}
+    let cli = Cli::parse();
 
-impl BranchInfo {
-    fn new(id: usize, i_on_start: usize) -> Self {
-        Self { id, i_on_start }
+    // exporting without opening the window
+    if let Some(output) = cli.output {
+        let app_config = match &cli.config {
+            Some(path) => config_file::load(path).unwrap_or_else(|err| panic!("{}", err)),
+            None => AppConfig::new(DEEPS.to_vec()),
+        };
+        let trees = build_trees_3d(&app_config);
+        export::export_trees(&output, &trees, &app_config)
+            .unwrap_or_else(|err| panic!("Could not export in {}: {}", output.display(), err));
+        return;
     }
 
-    fn main_branch() -> Self {
-        Self {
-            id: 0,
-            i_on_start: 0,
-        }
-    }
+    nannou::app(model)
+        .update(update)
+        .event(event)
+        .simple_window(view)
+        .run();
 }
 
 struct Model {
-    previous_i: usize,
-    progress_i: usize,
-    dots_pro_seconds: usize,
+    playback: Playback,
     app_config: AppConfig,
-    // the tree has the tree info and the vector of branches, that should be animated and
-    // queued branches
-    trees: Vec<(LsystemTree, Vec<BranchInfo>, VecDeque<BranchInfo>)>,
-    last_drawed_total_dot: Vec<Point2>,
-    max_branches: usize,
+    trees: Vec<SceneTree>,
+    wind: Option<Wind>,
     animate: bool,
+    camera: OrbitCamera,
+    // the depths of the projected trees, that are drawn from the far to the near one
+    depth_layers: DepthLayers,
+    pan_zoom: PanZoom,
+    panel: Panel,
+    rebuilder: Rebuilder,
+    // the config file, that is loaded again after every change
+    config_watcher: Option<ConfigWatcher>,
+    // the error of the config file or of the building, that is shown over the trees
+    error: Option<String>,
+    // the message of the last save with the time, when it was saved
+    status: Option<(String, f32)>,
+    // the updates until the captured frame is drawn, the message isn't shown in the image
+    capture_updates: usize,
+    // H shows the config and the stats
+    show_debug: bool,
+    stats: Stats,
+    // the press of the left button, that plants a tree, if it is released without dragging
+    click: Option<Point2>,
 }
 
-fn model(_app: &App) -> Model {
-    let deeps = vec![8];
-    let app_config = AppConfig::new(deeps.clone());
-    let lsystem_builder = LsystemBuilder::new(&app_config.config.clone());
+// the state of the animation, that is counted in every update
+#[derive(Default)]
+struct Stats {
+    growing: usize,
+    queued: usize,
+    frame_time: std::time::Duration,
+}
+
+// a tree in the window with everything needed to animate it
+struct SceneTree {
+    // the tree of the turtle, `tree` is its projection by the camera
+    tree_3d: LsystemTree3D,
+    tree: LsystemTree,
+    // the tree without the wind, the wind bends `tree` from it in every update
+    rest_tree: Option<LsystemTree>,
+    // the plan of its growing
+    animation: TreeAnimation,
+    // the grown branches, that stay visible
+    grown_mesh: GrownMesh,
+    // the tessellated shapes by their names
+    shape_meshes: HashMap<String, ShapeMesh>,
+    // the progress, on which the tree starts to grow, the planted trees grow from their click
+    start: f32,
+}
+
+impl SceneTree {
+    // the progress of its own animation
+    fn progress(&self, progress: f32) -> f32 {
+        progress - self.start
+    }
+}
+
+// builds the trees of every deep and places them next to each other
+fn build_trees_3d(app_config: &AppConfig) -> Vec<LsystemTree3D> {
     let mut trees = app_config
         .deeps
         .iter()
-        .map(|deep| lsystem_builder.build_tree(deep))
+        .enumerate()
+        .map(|(i, deep)| {
+            // every tree can have its own color scheme and has its own seed, so the same
+            // grammars give different trees
+            let mut config = app_config.config.clone();
+            config.jitter.seed = config.jitter.seed.wrapping_add(i as u64);
+            if let Some(color_scheme) = app_config.color_schemes.get(i) {
+                config.color_scheme = color_scheme.clone();
+            }
+            LsystemBuilder::new(&config).build_tree_3d(deep)
+        })
         .collect::<Vec<_>>();
 
     let delta = app_config.start_point_delta.unwrap_or(pt2(200.0, 0.0));
@@ -70,134 +148,546 @@ fn model(_app: &App) -> Model {
         tree.move_tree(start_point + delta * i as f32);
     });
 
-    let trees = trees
-        .into_iter()
-        .map(|tree| return (tree, vec![BranchInfo::main_branch()], vec![].into()))
-        .collect::<Vec<(LsystemTree, Vec<BranchInfo>, VecDeque<BranchInfo>)>>();
+    // the planted trees with their own seeds after the trees of the deeps
+    for (i, planted) in app_config.planted.iter().enumerate() {
+        trees.push(build_planted(
+            app_config,
+            planted,
+            app_config.deeps.len() + i,
+        ));
+    }
+
+    trees
+}
+
+// builds the planted tree on its point, the index of the tree is added to the seed
+fn build_planted(app_config: &AppConfig, planted: &Planted, i: usize) -> LsystemTree3D {
+    let mut config = app_config
+        .planted_config(planted)
+        .unwrap_or_else(|| panic!("There is no preset named {}", planted.preset));
+    config.jitter.seed = config.jitter.seed.wrapping_add(i as u64);
+    let mut tree = LsystemBuilder::new(&config).build_tree_3d(&planted.deep);
+    tree.move_tree(planted.point);
+    tree
+}
+
+fn model(_app: &App) -> Model {
+    let config_path = Cli::parse().config;
+    // with a wrong config file the viewer starts with the constants and shows the error
+    let (app_config, error) = match &config_path {
+        Some(path) => match config_file::load(path) {
+            Ok(app_config) => (app_config, None),
+            Err(err) => (AppConfig::new(DEEPS.to_vec()), Some(err)),
+        },
+        None => (AppConfig::new(DEEPS.to_vec()), None),
+    };
+    // the panic of the builder, that the validation doesn't find, starts it with the constants too
+    let (app_config, trees, error) = match build_catching(|| build_trees_3d(&app_config)) {
+        Ok(trees) => (app_config, trees, error),
+        Err(err) => {
+            let app_config = AppConfig::new(DEEPS.to_vec());
+            let trees = build_trees_3d(&app_config);
+            (app_config, trees, Some(err))
+        }
+    };
+
+    // the camera turns around the middle of all trees
+    let (min, max) = trees.iter().fold(
+        (pt2(f32::MAX, f32::MAX), pt2(f32::MIN, f32::MIN)),
+        |(min, max), tree_3d| {
+            let (tree_min, tree_max) = tree_3d.tree.bounds();
+            (min.min(tree_min), max.max(tree_max))
+        },
+    );
+    let camera = OrbitCamera::new(((min + max) / 2.0).extend(0.0));
+    let projection = camera.projection(1.0, hex_to_rgb(&app_config.bg_color));
+
+    let wind = app_config.config.wind_power.map(Wind::new);
+    let trees = scene_trees(trees, &app_config, &projection);
+    let depth_layers = DepthLayers::new(trees.iter().map(|scene_tree| &scene_tree.tree));
 
     Model {
-        previous_i: 0,
-        progress_i: 0,
-        dots_pro_seconds: app_config.dots_pro_second,
+        playback: Playback::new(app_config.dots_pro_second, duration(&trees)),
         app_config,
         trees,
-        last_drawed_total_dot: vec![],
-        max_branches: 5000 / deeps.len(),
+        wind,
         animate: ANIMATE,
+        camera,
+        depth_layers,
+        pan_zoom: PanZoom::new(),
+        panel: Panel::new(),
+        rebuilder: Rebuilder::new(),
+        config_watcher: config_path.map(ConfigWatcher::new),
+        error,
+        status: None,
+        capture_updates: 0,
+        show_debug: false,
+        stats: Stats::default(),
+        click: None,
+    }
+}
+
+// the trees with their growing plans
+fn scene_trees(
+    trees: Vec<LsystemTree3D>,
+    app_config: &AppConfig,
+    projection: &Projection,
+) -> Vec<SceneTree> {
+    let max_branches = MAX_ANIMATED_BRANCHES / trees.len().max(1);
+    // the trees of the deeps grow from the start
+    let starts = app_config
+        .deeps
+        .iter()
+        .map(|_| 0.0)
+        .chain(app_config.planted.iter().map(|planted| planted.start));
+    trees
+        .into_iter()
+        .zip(starts)
+        .map(|(tree_3d, start)| scene_tree(tree_3d, start, max_branches, app_config, projection))
+        .collect()
+}
+
+fn scene_tree(
+    tree_3d: LsystemTree3D,
+    start: f32,
+    max_branches: usize,
+    app_config: &AppConfig,
+    projection: &Projection,
+) -> SceneTree {
+    let tree = projection.project(&tree_3d);
+    SceneTree {
+        animation: TreeAnimation::new(&tree_3d.tree, max_branches, app_config.scheduling),
+        rest_tree: app_config.config.wind_power.map(|_| tree.clone()),
+        tree,
+        grown_mesh: GrownMesh::new(),
+        shape_meshes: ShapeMesh::of_tree(&tree_3d.tree),
+        tree_3d,
+        start,
+    }
+}
+
+// the projection of the camera and the zoom, by which the trees are shown
+fn projection(model: &Model) -> Projection {
+    model.camera.projection(
+        model.pan_zoom.width_scale(),
+        hex_to_rgb(&model.app_config.bg_color),
+    )
+}
+
+// the progress of the shown trees, without the animation the trees are shown fully grown
+fn shown_progress(model: &Model) -> f32 {
+    match model.animate {
+        true => model.playback.progress,
+        false => f32::INFINITY,
     }
 }
 
-// todo how to make the drawing not to disappear
-fn update(_app: &App, model: &mut Model, _update: Update) {
-    model.previous_i = model.progress_i;
-    model.progress_i += model.dots_pro_seconds;
+// the progress, on which all trees are grown
+fn duration(trees: &[SceneTree]) -> f32 {
+    trees
+        .iter()
+        .map(|scene_tree| scene_tree.start + scene_tree.animation.duration() as f32)
+        .fold(0.0, f32::max)
+}
+
+// a left click plants the preset and the depth selected in the panel on the clicked point, the
+// tree grows from this moment, Z removes the last planted tree and C saves the scene with the
+// config
+fn plant(model: &mut Model, mouse: Point2) {
+    let app_config = &model.app_config;
+    let planted = Planted {
+        point: model.camera.unproject(model.pan_zoom.to_world(mouse)),
+        preset: PRESETS[app_config.plant_preset].0.to_string(),
+        deep: app_config.plant_deep,
+        start: model.playback.progress,
+    };
+    model.app_config.planted.push(planted);
+    // the tree is added, when it is built
+    let projection = projection(model);
+    model.rebuilder.plant(&model.app_config, projection);
+}
 
-    model
+fn undo_planting(model: &mut Model) {
+    if model.app_config.planted.pop().is_none() {
+        return;
+    }
+    // the trees, that are being built, can have the planted tree, so they are built again
+    if model.rebuilder.is_building() {
+        let projection = projection(model);
+        model.rebuilder.request(&model.app_config, projection);
+    } else {
+        model.trees.pop();
+        trees_changed(model);
+    }
+}
+
+// the new trees change the duration and the depths of all trees
+fn trees_changed(model: &mut Model) {
+    model.playback.duration = duration(&model.trees);
+    model.playback.set_progress(model.playback.progress);
+    let depth_layers = DepthLayers::new(model.trees.iter().map(|scene_tree| &scene_tree.tree));
+    // the tessellated branches are in the layers of the old depths
+    if depth_layers != model.depth_layers {
+        model.depth_layers = depth_layers;
+        for scene_tree in model.trees.iter_mut() {
+            scene_tree.grown_mesh = GrownMesh::new();
+        }
+    }
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
+    // the progress depends on the elapsed time, not on the count of updates,
+    // so the animation has the same speed on every machine
+    model.playback.update(update.since_last);
+    model.stats.frame_time = update.since_last;
+    model.capture_updates = model.capture_updates.saturating_sub(1);
+
+    // the changed config is built on the background thread and the trees are swapped, when they
+    // are ready
+    if model.panel.take_changed() {
+        let projection = projection(model);
+        model.rebuilder.request(&model.app_config, projection);
+    }
+    // the saved config file is built like the changes of the panel, the camera stays
+    if let Some(watcher) = model.config_watcher.as_mut() {
+        if watcher.changed(update.since_last) {
+            match config_file::reload(watcher.path(), &model.app_config) {
+                Ok(app_config) => {
+                    model.wind = app_config.config.wind_power.map(Wind::new);
+                    model.playback.dots_pro_second = app_config.dots_pro_second;
+                    model.app_config = app_config;
+                    let projection = projection(model);
+                    model.rebuilder.request(&model.app_config, projection);
+                }
+                Err(err) => model.error = Some(err),
+            }
+        }
+    }
+    while let Some((built, built_projection)) = model.rebuilder.try_take() {
+        match built {
+            Ok(Built::Scene(trees)) => {
+                model.trees = trees;
+                model.error = None;
+            }
+            Ok(Built::Plant(scene_tree)) => model.trees.push(*scene_tree),
+            Err(err) => {
+                model.error = Some(err);
+                continue;
+            }
+        }
+        trees_changed(model);
+        // the camera was moved, while the trees were built
+        if built_projection != projection(model) {
+            model.camera.refresh();
+        }
+    }
+
+    // the widths, that are kept on the screen, change with the zoom
+    if model.pan_zoom.take_changed() {
+        model.camera.refresh();
+    }
+    if model.camera.take_changed() {
+        let projection = projection(model);
+        for scene_tree in model.trees.iter_mut() {
+            let tree = projection.project(&scene_tree.tree_3d);
+            if scene_tree.rest_tree.is_some() {
+                scene_tree.rest_tree = Some(tree.clone());
+            }
+            scene_tree.tree = tree;
+            // the projected branches have to be tessellated again
+            scene_tree.grown_mesh = GrownMesh::new();
+        }
+        model.depth_layers =
+            DepthLayers::new(model.trees.iter().map(|scene_tree| &scene_tree.tree));
+    }
+
+    let shown_progress = shown_progress(model);
+    for scene_tree in model.trees.iter_mut() {
+        if let (Some(wind), Some(rest_tree)) = (&model.wind, &scene_tree.rest_tree) {
+            scene_tree
+                .tree
+                .bend_from(rest_tree, |dot| wind.offset(app.time, dot));
+        }
+
+        // the finished branches stay visible in the mesh, it is built from the tree without the
+        // wind and the wind moves its points, when it is drawn
+        let progress = scene_tree.progress(shown_progress);
+        let rest_tree = scene_tree.rest_tree.as_ref().unwrap_or(&scene_tree.tree);
+        scene_tree.grown_mesh.update(
+            rest_tree,
+            &scene_tree.animation,
+            progress,
+            &model.depth_layers,
+        );
+    }
+
+    model.stats.growing = 0;
+    model.stats.queued = 0;
+    for scene_tree in model.trees.iter() {
+        let animation = &scene_tree.animation;
+        let progress = scene_tree.progress(shown_progress);
+        model.stats.growing += animation
+            .branches_to_animate_at(&scene_tree.tree, progress)
+            .len();
+        model.stats.queued += animation.queued_branches_at(progress).len();
+    }
+}
+
+// the counts of the trees and the animation for the debug info
+fn debug_stats(model: &Model) -> String {
+    let symbols = model
         .trees
-        .iter_mut()
-        .for_each(|(tree, branches_to_animate, queued_branches)| {
-            let mut new_founded_branches = vec![];
-            branches_to_animate.retain(|branch_info| {
-                // updating the new opened branches to animate, if there are some
-                if let Some(branch_dots) = tree.branches.get(&branch_info.id) {
-                    // getting the index in dependence from the index on the start of drawing, but not
-                    // bigger as the len of the dots itself
-                    let to_index =
-                        (model.progress_i - branch_info.i_on_start).min(branch_dots.len());
-                    if to_index != branch_dots.len() {
-                        // checking if in the new drawed dots are some connected branches
-                        for dot in &branch_dots[to_index - model.dots_pro_seconds..to_index] {
-                            model.last_drawed_total_dot.push(dot.pos);
-                            for &branch_id in &dot.connected_branches_id {
-                                // and push new branches in the branches to temp branches
-                                new_founded_branches
-                                    .push(BranchInfo::new(branch_id, model.progress_i));
-                            }
-                        }
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
+        .iter()
+        .map(|scene_tree| scene_tree.tree_3d.symbols_count)
+        .sum::<usize>();
+    let branches = model
+        .trees
+        .iter()
+        .map(|scene_tree| scene_tree.tree.branches.len())
+        .sum::<usize>();
+    let segments = model
+        .trees
+        .iter()
+        .flat_map(|scene_tree| scene_tree.tree.branches.values())
+        .map(|branch| branch.len())
+        .sum::<usize>();
+    let frame_ms = model.stats.frame_time.as_secs_f32() * 1000.0;
+
+    format!(
+        "Deeps: {:?} Planted: {}\nSymbols: {}\nBranches: {} Segments: {}\nProgress: {:.0} / {:.0}\n\
+         Growing: {} Queued: {}\nFrame time: {:.1} ms ({:.0} fps)",
+        model.app_config.deeps,
+        model.app_config.planted.len(),
+        symbols,
+        branches,
+        segments,
+        model.playback.progress,
+        model.playback.duration,
+        model.stats.growing,
+        model.stats.queued,
+        frame_ms,
+        1000.0 / frame_ms.max(0.001),
+    )
+}
+
+fn event(app: &App, model: &mut Model, event: Event) {
+    if let Event::WindowEvent {
+        simple: Some(window_event),
+        ..
+    } = event
+    {
+        match window_event {
+            KeyPressed(key) => {
+                model.panel.key_pressed(key, &mut model.app_config);
+                // the arrows choose the parameter in the open panel
+                if !(model.panel.visible && matches!(key, Key::Up | Key::Down)) {
+                    model.playback.key_pressed(key);
                 }
-            });
-
-            // we render only some number of branches at the time (optimisation)
-            while branches_to_animate.len() != model.max_branches {
-                // we check if there are some queued branches, because they came earlier then the new ones
-                if queued_branches.len() != 0 {
-                    let mut temp = queued_branches.pop_front().unwrap();
-                    // we change the start i of them
-                    temp.i_on_start = model.progress_i;
-                    branches_to_animate.push(temp);
-                // if there are no queued branches, we push the new one
-                } else if new_founded_branches.len() != 0 {
-                    branches_to_animate.push(new_founded_branches.pop().unwrap());
-                } else {
-                    break;
+                model.camera.key_pressed(key);
+                match key {
+                    Key::H => model.show_debug = !model.show_debug,
+                    Key::Z => undo_planting(model),
+                    _ => {}
                 }
+                model
+                    .pan_zoom
+                    .key_pressed(key, app.window_rect(), trees_bounds(&model.trees));
+                save_by_key(app, model, key);
             }
-
-            // if there are some new branches, we dont added to the animation, we put them in the queue
-            while new_founded_branches.len() != 0 {
-                queued_branches.push_back(new_founded_branches.pop().unwrap())
+            MousePressed(MouseButton::Left) => {
+                let mouse = app.mouse.position();
+                let win = app.window_rect();
+                // dragging outside of the panel and the scrub bar turns the camera, the click
+                // without dragging plants a tree
+                if !model.panel.mouse_pressed(win, mouse, &mut model.app_config)
+                    && !model.playback.mouse_pressed(win, mouse)
+                {
+                    model.camera.mouse_pressed(mouse);
+                    model.click = Some(mouse);
+                }
+            }
+            MousePressed(MouseButton::Right) => model.pan_zoom.mouse_pressed(app.mouse.position()),
+            MouseMoved(pos) => {
+                model.playback.mouse_moved(app.window_rect(), pos);
+                model.camera.mouse_moved(pos);
+                model.pan_zoom.mouse_moved(pos);
             }
-        });
+            MouseReleased(MouseButton::Left) => {
+                model.playback.mouse_released();
+                model.camera.mouse_released();
+                if let Some(click) = model.click.take() {
+                    if click.distance(app.mouse.position()) < CLICK_DISTANCE {
+                        plant(model, click);
+                    }
+                }
+            }
+            MouseReleased(MouseButton::Right) => model.pan_zoom.mouse_released(),
+            MouseWheel(delta, _) => model.pan_zoom.mouse_wheel(delta, app.mouse.position()),
+            _ => {}
+        }
+    }
+}
+
+// S saves the window as a png, V the trees as they are seen as an svg and C the config as a json
+// config file, the names have the time, so nothing is overwritten
+fn save_by_key(app: &App, model: &mut Model, key: Key) {
+    let name = format!("trees_{}", timestamp());
+    let saved = match key {
+        Key::S => {
+            let path = PathBuf::from(format!("{}.png", name));
+            // the frame is written, when it is drawn
+            app.main_window().capture_frame(&path);
+            model.capture_updates = 2;
+            Ok(path)
+        }
+        Key::V => {
+            let path = PathBuf::from(format!("{}.svg", name));
+            let trees = model
+                .trees
+                .iter()
+                .map(|scene_tree| scene_tree.tree.clone())
+                .collect::<Vec<LsystemTree>>();
+            export::export_svg(&path, &trees).map(|_| path)
+        }
+        Key::C => {
+            let path = PathBuf::from(format!("{}.json", name));
+            config_file::save(&path, &model.app_config).map(|_| path)
+        }
+        _ => return,
+    };
+
+    match saved {
+        Ok(path) => model.status = Some((format!("Saved {}", path.display()), app.time)),
+        Err(err) => model.error = Some(format!("Could not save: {}", err)),
+    }
+}
+
+// the lower left and the upper right corner of the trees on the screen
+fn trees_bounds(trees: &[SceneTree]) -> (Point2, Point2) {
+    trees.iter().fold(
+        (pt2(f32::MAX, f32::MAX), pt2(f32::MIN, f32::MIN)),
+        |(min, max), scene_tree| {
+            let (tree_min, tree_max) = scene_tree.tree.bounds();
+            (min.min(tree_min), max.max(tree_max))
+        },
+    )
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
+    // the trees are panned and zoomed, the controls stay on their place
+    let scene = model.pan_zoom.transform(&draw);
 
-    // let dots = model.trees[0]
-    //     .0
-    //     .dots_cutted
-    //     .iter()
-    //     .map(|b_dot| return b_dot.pos)
-    //     .collect::<Vec<Point2>>();
-    // draw.polyline()
-    //     .weight(model.app_config.config.line_weight)
-    //     .points(dots[..model.progress_i].iter().cloned())
-    //     .color(hex_to_rgb(&model.app_config.config.main_color));
+    draw_trees(&scene, model, app.time);
 
-    for tree_info in model.trees.iter() {
-        match model.animate {
-            true => draw_branches_to_animate(&tree_info.0, &tree_info.1, &draw, model),
-            false => draw_full_tree(&tree_info.0, &draw, model),
+    if model.animate {
+        model.playback.draw(
+            &draw,
+            app.window_rect(),
+            hex_to_rgb(&model.app_config.config.main_color),
+            &format!(
+                "growing: {} queued: {}",
+                model.stats.growing, model.stats.queued
+            ),
+        );
+    }
+    if model.show_debug {
+        debug_info(
+            &draw,
+            app.window_rect(),
+            &model.app_config.config,
+            &debug_stats(model),
+        );
+    }
+    model.panel.draw(
+        &draw,
+        app.window_rect(),
+        &model.app_config,
+        model.rebuilder.is_building(),
+    );
+    if let Some((status, time)) = &model.status {
+        if app.time - time < STATUS_SECONDS && model.capture_updates == 0 {
+            status_overlay(&draw, app.window_rect(), status);
         }
     }
+    if let Some(error) = &model.error {
+        error_overlay(&draw, app.window_rect(), error);
+    }
     draw.to_frame(app, &frame).unwrap();
 }
 
-fn draw_full_tree(tree: &LsystemTree, draw: &Draw, model: &Model) {
-    draw.polyline()
-        .weight(model.app_config.config.line_weight)
-        .points(
-            tree.dots_cutted
-                .iter()
-                .map(|branch_dot| return branch_dot.pos)
-                .collect::<Vec<Point2>>()
-                .iter()
-                .cloned(),
-        )
-        .color(hex_to_rgb(&model.app_config.config.main_color));
-}
-fn draw_branches_to_animate(
-    tree: &LsystemTree,
-    branches_to_animate: &Vec<BranchInfo>,
-    draw: &Draw,
-    model: &Model,
-) {
-    for branch_info in branches_to_animate {
-        if let Some(branch) = tree.branches.get(&branch_info.id) {
-            let to_index = (model.progress_i - branch_info.i_on_start).min(branch.len());
-            if to_index > 0 {
-                let dots = branch[..to_index].iter().map(|br_dot| br_dot.pos);
-                draw.polyline()
-                    .weight(model.app_config.config.line_weight)
-                    .points(dots)
-                    .color(hex_to_rgb(&model.app_config.config.main_color));
+// a part of a tree, that is drawn over the grown meshes of its depth layer
+enum Primitive<'a> {
+    Stroke(Stroke),
+    Polygon(&'a Polygon),
+    Circle(&'a Circle),
+    Shape(&'a ShapeInstance, &'a ShapeMesh),
+}
+
+// draws all trees in the depth layers from the far to the near one, in every layer the grown
+// meshes first and then the growing branches, the polygons, the circles and the shapes sorted by
+// their depth, so the near parts of every tree are drawn over the far parts of the other ones
+fn draw_trees(draw: &Draw, model: &Model, time: f32) {
+    let mut layers: Vec<Vec<(f32, Primitive)>> = (0..DEPTH_LAYERS).map(|_| vec![]).collect();
+    let mut push =
+        |depth: f32, primitive| layers[model.depth_layers.layer(depth)].push((depth, primitive));
+    for scene_tree in model.trees.iter() {
+        let tree = &scene_tree.tree;
+        let animation = &scene_tree.animation;
+        let progress = scene_tree.progress(shown_progress(model));
+        for (id, stroke) in animation.visible_branches(tree, progress) {
+            push(tree.branch_depth(&id), Primitive::Stroke(stroke));
+        }
+        for polygon in animation.visible_polygons(tree, progress) {
+            push(polygon.depth(), Primitive::Polygon(polygon));
+        }
+        for circle in animation.visible_circles(tree, progress) {
+            push(circle.z, Primitive::Circle(circle));
+        }
+        for instance in animation.visible_shapes(tree, progress) {
+            if let Some(mesh) = scene_tree.shape_meshes.get(&instance.shape) {
+                push(instance.z, Primitive::Shape(instance, mesh));
             }
         }
     }
+
+    let offset = |anchor: &Anchor| match &model.wind {
+        Some(wind) => wind.offset_at(time, anchor.pos, anchor.arc_length, anchor.width),
+        None => Vec2::ZERO,
+    };
+    for (i, layer) in layers.iter_mut().enumerate() {
+        for scene_tree in model.trees.iter() {
+            scene_tree.grown_mesh.draw_layer(i, draw, offset);
+        }
+        // the same depths keep their order, so the 2D trees are drawn as they grow
+        layer.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, primitive) in layer.iter() {
+            draw_primitive(draw, primitive);
+        }
+    }
+}
+
+fn draw_primitive(draw: &Draw, primitive: &Primitive) {
+    match primitive {
+        Primitive::Stroke(stroke) => grown_mesh::draw_stroke(draw, stroke),
+        Primitive::Polygon(polygon) => {
+            draw.polygon()
+                .points(polygon.points.iter().cloned())
+                .color(polygon.color);
+        }
+        Primitive::Circle(circle) => {
+            draw.ellipse()
+                .xy(circle.pos)
+                .radius(circle.radius)
+                .color(circle.color);
+        }
+        // the mesh of the shape is placed by the transform of the draw
+        Primitive::Shape(instance, mesh) => {
+            let draw = draw
+                .xy(instance.pos)
+                .rotate(instance.rotation)
+                .scale(instance.scale);
+            mesh.draw(&draw, instance.color);
+        }
+    }
 }
@@ -1,5 +1,7 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use nannou::{
-    color::{rgb, Rgb, BLACK},
+    color::{rgb, rgba, Rgb, BLACK},
     geom::Rect,
     Draw,
 };
@@ -21,11 +23,88 @@ pub fn hex_to_rgb(hex: &str) -> Rgb {
     rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
 }
 
-pub fn debug_info(draw: &Draw, win: Rect, lsystem: &LsystemConfig) {
-    let pad = 6.0;
-    draw.text(&format!("Lsystem config\n\n{}", lsystem))
+// the current time in utc as "YYYYMMDD_HHMMSS_mmm" for the names of the saved files, the
+// milliseconds keep the files of the quick saves apart
+pub fn timestamp() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64);
+    let secs = millis / 1000;
+    let (days, time) = (secs / 86400, secs % 86400);
+
+    // the civil date from the days since 1970-01-01 by the algorithm of Howard Hinnant
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        millis % 1000
+    )
+}
+
+// true for the "#RRGGBB" colors, that hex_to_rgb accepts
+pub fn is_hex_color(hex: &str) -> bool {
+    hex.len() == 7 && hex.starts_with('#') && hex[1..].chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+// the error over the whole window, the trees stay as they were before the error
+pub fn error_overlay(draw: &Draw, win: Rect, error: &str) {
+    let pad = 12.0;
+    draw.rect()
+        .xy(win.xy())
+        .wh(win.wh())
+        .color(rgba(1.0, 1.0, 1.0, 0.7));
+    draw.text(&format!("Error\n\n{}", error))
         .h(win.pad(pad).h())
         .w(win.pad(pad).w())
+        .font_size(14)
+        .align_text_top()
+        .left_justify()
+        .color(rgb(0.8, 0.0, 0.0));
+}
+
+// the short message in the lower left corner, like the saved file
+pub fn status_overlay(draw: &Draw, win: Rect, status: &str) {
+    let pad = 6.0;
+    let area = Rect::from_x_y_w_h(win.x(), win.bottom() + 14.0, win.w(), 28.0);
+    draw.rect()
+        .xy(area.xy())
+        .wh(area.wh())
+        .color(rgba(1.0, 1.0, 1.0, 0.7));
+    draw.text(status)
+        .xy(area.xy())
+        .w(area.pad(pad).w())
+        .font_size(14)
+        .left_justify()
+        .color(BLACK);
+}
+
+// the config and the stats on the right half of the window
+pub fn debug_info(draw: &Draw, win: Rect, lsystem: &LsystemConfig, stats: &str) {
+    let pad = 6.0;
+    let area = Rect::from_x_y_w_h(win.right() - win.w() / 4.0, win.y(), win.w() / 2.0, win.h());
+    draw.rect()
+        .xy(area.xy())
+        .wh(area.wh())
+        .color(rgba(1.0, 1.0, 1.0, 0.7));
+    draw.text(&format!("{}\n\nLsystem config\n\n{}", stats, lsystem))
+        .xy(area.xy())
+        .h(area.pad(pad).h())
+        .w(area.pad(pad).w())
         .line_spacing(pad)
         .font_size(14)
         .align_text_top()
@@ -0,0 +1,118 @@
+use nannou::{event::MouseScrollDelta, prelude::*};
+
+// how much one line of the mouse wheel zooms
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 50.0;
+// the free space around the trees, when they are fitted in the window
+const FIT_PAD: f32 = 20.0;
+
+// the 2D view of the window, that is the transform of the draw of the trees: the mouse wheel
+// zooms around the cursor, the drag with the right button pans, Home fits the trees in the
+// window, 0 resets the view and W keeps the width of the branches on the screen while zooming
+pub struct PanZoom {
+    // the place of the world origin on the screen
+    offset: Vec2,
+    pub zoom: f32,
+    // the branches have the same width on the screen with every zoom
+    pub constant_weight: bool,
+    // the last mouse position of the drag
+    panning: Option<Point2>,
+    // the widths of the branches have to be scaled again
+    changed: bool,
+}
+
+impl PanZoom {
+    pub fn new() -> PanZoom {
+        PanZoom {
+            offset: Vec2::ZERO,
+            zoom: 1.0,
+            constant_weight: false,
+            panning: None,
+            changed: false,
+        }
+    }
+
+    // the draw of the trees, the draw of the panels stays without the transform
+    pub fn transform(&self, draw: &Draw) -> Draw {
+        draw.translate(self.offset.extend(0.0)).scale(self.zoom)
+    }
+
+    // the factor of the widths, that keeps them on the screen
+    pub fn width_scale(&self) -> f32 {
+        if self.constant_weight {
+            1.0 / self.zoom
+        } else {
+            1.0
+        }
+    }
+
+    // the point of the world under the point of the screen
+    pub fn to_world(&self, screen: Point2) -> Point2 {
+        (screen - self.offset) / self.zoom
+    }
+
+    // returns true once after every change, that changes the widths
+    pub fn take_changed(&mut self) -> bool {
+        let changed = self.changed;
+        self.changed = false;
+        changed
+    }
+
+    // the lower left and the upper right corner of the trees are given for the fitting
+    pub fn key_pressed(&mut self, key: Key, win: Rect, bounds: (Point2, Point2)) {
+        match key {
+            Key::Home => self.fit(win, bounds),
+            Key::Key0 => {
+                self.offset = Vec2::ZERO;
+                self.set_zoom(1.0);
+            }
+            Key::W => {
+                self.constant_weight = !self.constant_weight;
+                self.changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn fit(&mut self, win: Rect, (min, max): (Point2, Point2)) {
+        let size = (max - min).max(Vec2::splat(1.0));
+        let zoom = ((win.w() - 2.0 * FIT_PAD) / size.x).min((win.h() - 2.0 * FIT_PAD) / size.y);
+        self.set_zoom(zoom);
+        self.offset = win.xy() - (min + max) / 2.0 * self.zoom;
+    }
+
+    fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if self.constant_weight {
+            self.changed = true;
+        }
+    }
+
+    pub fn mouse_pressed(&mut self, mouse: Point2) {
+        self.panning = Some(mouse);
+    }
+
+    pub fn mouse_moved(&mut self, mouse: Point2) {
+        if let Some(last) = self.panning {
+            self.offset += mouse - last;
+            self.panning = Some(mouse);
+        }
+    }
+
+    pub fn mouse_released(&mut self) {
+        self.panning = None;
+    }
+
+    // zooms so that the point of the world under the cursor stays under it
+    pub fn mouse_wheel(&mut self, delta: MouseScrollDelta, mouse: Point2) {
+        let lines = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            // about the height of a line in pixels
+            MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 20.0,
+        };
+        let world = self.to_world(mouse);
+        self.set_zoom(self.zoom * ZOOM_STEP.powf(lines));
+        self.offset = mouse - world * self.zoom;
+    }
+}
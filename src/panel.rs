@@ -0,0 +1,311 @@
+use nannou::prelude::*;
+
+use crate::{config::AppConfig, constants::PRESETS, lsystem::ColorMode};
+
+const ROW_HEIGHT: f32 = 18.0;
+const PANEL_WIDTH: f32 = 240.0;
+const PANEL_PAD: f32 = 12.0;
+const BUTTON_WIDTH: f32 = 18.0;
+
+// the parameters of the config, that can be changed in the panel
+#[derive(Clone, Copy)]
+enum Param {
+    Rotation,
+    Step,
+    ScaleDelta,
+    ScaleMin,
+    Depth,
+    LineWeight,
+    Susceptibility,
+    ColorMode,
+    MainColor,
+    PlantPreset,
+    PlantDepth,
+}
+
+const PARAMS: [Param; 11] = [
+    Param::Rotation,
+    Param::Step,
+    Param::ScaleDelta,
+    Param::ScaleMin,
+    Param::Depth,
+    Param::LineWeight,
+    Param::Susceptibility,
+    Param::ColorMode,
+    Param::MainColor,
+    Param::PlantPreset,
+    Param::PlantDepth,
+];
+
+const COLOR_MODES: [ColorMode; 5] = [
+    ColorMode::Single,
+    ColorMode::Symbols,
+    ColorMode::Depth,
+    ColorMode::ArcLength,
+    ColorMode::Order,
+];
+
+// the panel in the upper left corner to change the config while the viewer runs:
+// tab shows and hides it, up/down choose the parameter and [ / ] change it, the - and + buttons
+// change the parameter of their row
+pub struct Panel {
+    pub visible: bool,
+    selected: usize,
+    // the trees have to be built again
+    changed: bool,
+}
+
+impl Panel {
+    pub fn new() -> Panel {
+        Panel {
+            visible: false,
+            selected: 0,
+            changed: false,
+        }
+    }
+
+    // returns true once after every change of the config
+    pub fn take_changed(&mut self) -> bool {
+        let changed = self.changed;
+        self.changed = false;
+        changed
+    }
+
+    pub fn key_pressed(&mut self, key: Key, app_config: &mut AppConfig) {
+        if key == Key::Tab {
+            self.visible = !self.visible;
+            return;
+        }
+        if !self.visible {
+            return;
+        }
+        match key {
+            Key::Up => self.selected = (self.selected + PARAMS.len() - 1) % PARAMS.len(),
+            Key::Down => self.selected = (self.selected + 1) % PARAMS.len(),
+            Key::LBracket => self.change(PARAMS[self.selected], app_config, -1.0),
+            Key::RBracket => self.change(PARAMS[self.selected], app_config, 1.0),
+            _ => {}
+        }
+    }
+
+    // returns true, if the click was on the panel
+    pub fn mouse_pressed(&mut self, win: Rect, mouse: Point2, app_config: &mut AppConfig) -> bool {
+        if !self.visible || !panel_rect(win).contains(mouse) {
+            return false;
+        }
+        for (i, param) in PARAMS.iter().enumerate() {
+            let row = row_rect(win, i);
+            if !row.contains(mouse) {
+                continue;
+            }
+            self.selected = i;
+            if minus_rect(row).contains(mouse) {
+                self.change(*param, app_config, -1.0);
+            } else if plus_rect(row).contains(mouse) {
+                self.change(*param, app_config, 1.0);
+            }
+        }
+        true
+    }
+
+    fn change(&mut self, param: Param, app_config: &mut AppConfig, direction: f32) {
+        let config = &mut app_config.config;
+        match param {
+            Param::Rotation => {
+                config.rotation_factor = (config.rotation_factor.to_degrees() + direction)
+                    .clamp(0.0, 360.0)
+                    .to_radians()
+            }
+            Param::Step => {
+                let length = (config.start_direction.length() + direction).max(1.0);
+                config.start_direction = config.start_direction.normalize() * length;
+            }
+            Param::ScaleDelta => config.scale_delta += direction * 0.01,
+            Param::ScaleMin => config.scale_min = (config.scale_min + direction * 0.05).max(0.05),
+            Param::Depth => {
+                let deeps = app_config
+                    .deeps
+                    .iter()
+                    .map(|deep| (*deep as i32 + direction as i32).max(0) as usize)
+                    .collect::<Vec<_>>();
+                // the deeper trees, that would be too big, are not built
+                if !deeps.iter().all(|deep| config.fits(*deep)) {
+                    return;
+                }
+                app_config.deeps = deeps;
+            }
+            Param::LineWeight => {
+                config.line_weight = (config.line_weight + direction * 0.25).max(0.25)
+            }
+            Param::Susceptibility => {
+                config.susceptibility = (config.susceptibility + direction * 0.05).max(0.0)
+            }
+            Param::ColorMode => {
+                let i = COLOR_MODES
+                    .iter()
+                    .position(|mode| *mode == config.color_scheme.mode)
+                    .unwrap_or(0);
+                let mode = COLOR_MODES[cycle(i, COLOR_MODES.len(), direction)];
+                config.color_scheme.mode = mode;
+                // the own schemes of the trees change with it
+                for color_scheme in app_config.color_schemes.iter_mut() {
+                    color_scheme.mode = mode;
+                }
+            }
+            Param::MainColor => {
+                // the main color goes through the palette
+                let palette = &config.color_scheme.palette;
+                if !palette.is_empty() {
+                    let i = palette
+                        .iter()
+                        .position(|hex| *hex == config.main_color)
+                        .map_or(0, |i| cycle(i, palette.len(), direction));
+                    config.main_color = palette[i].clone();
+                }
+            }
+            // the selection is for the next click, the trees stay
+            Param::PlantPreset => {
+                app_config.plant_preset = cycle(app_config.plant_preset, PRESETS.len(), direction);
+                return;
+            }
+            Param::PlantDepth => {
+                let deep = (app_config.plant_deep as i32 + direction as i32).max(0) as usize;
+                let preset = PRESETS[app_config.plant_preset].0;
+                if app_config
+                    .preset_config(preset)
+                    .is_some_and(|config| config.fits(deep))
+                {
+                    app_config.plant_deep = deep;
+                }
+                return;
+            }
+        }
+        self.changed = true;
+    }
+
+    pub fn draw(&self, draw: &Draw, win: Rect, app_config: &AppConfig, building: bool) {
+        if !self.visible {
+            return;
+        }
+
+        let panel = panel_rect(win);
+        draw.rect()
+            .xy(panel.xy())
+            .wh(panel.wh())
+            .color(rgba(1.0, 1.0, 1.0, 0.8));
+
+        for (i, param) in PARAMS.iter().enumerate() {
+            let row = row_rect(win, i);
+            if i == self.selected {
+                draw.rect()
+                    .xy(row.xy())
+                    .wh(row.wh())
+                    .color(rgba(0.0, 0.0, 0.0, 0.1));
+            }
+            draw.text(&format!("{}: {}", name(*param), value(*param, app_config)))
+                .xy(row.xy())
+                .wh(row.wh())
+                .font_size(12)
+                .left_justify()
+                .color(BLACK);
+            for (button, text) in [(minus_rect(row), "-"), (plus_rect(row), "+")] {
+                draw.rect()
+                    .xy(button.xy())
+                    .wh(button.wh())
+                    .color(rgba(0.0, 0.0, 0.0, 0.15));
+                draw.text(text).xy(button.xy()).font_size(12).color(BLACK);
+            }
+        }
+
+        if building {
+            let row = row_rect(win, PARAMS.len());
+            draw.text("building...")
+                .xy(row.xy())
+                .wh(row.wh())
+                .font_size(12)
+                .left_justify()
+                .color(BLACK);
+        }
+    }
+}
+
+fn name(param: Param) -> &'static str {
+    match param {
+        Param::Rotation => "Rotation",
+        Param::Step => "Step",
+        Param::ScaleDelta => "Scale delta",
+        Param::ScaleMin => "Min scale",
+        Param::Depth => "Depth",
+        Param::LineWeight => "Line weight",
+        Param::Susceptibility => "Susceptibility",
+        Param::ColorMode => "Color mode",
+        Param::MainColor => "Main color",
+        Param::PlantPreset => "Plant preset",
+        Param::PlantDepth => "Plant depth",
+    }
+}
+
+fn value(param: Param, app_config: &AppConfig) -> String {
+    let config = &app_config.config;
+    match param {
+        Param::Rotation => format!("{:.0}°", config.rotation_factor.to_degrees()),
+        Param::Step => format!("{:.0}", config.start_direction.length()),
+        Param::ScaleDelta => format!("{:.2}", config.scale_delta),
+        Param::ScaleMin => format!("{:.2}", config.scale_min),
+        Param::Depth => format!("{:?}", app_config.deeps),
+        Param::LineWeight => format!("{:.2}", config.line_weight),
+        Param::Susceptibility => format!("{:.2}", config.susceptibility),
+        Param::ColorMode => config.color_scheme.mode.to_string(),
+        Param::MainColor => config.main_color.clone(),
+        Param::PlantPreset => PRESETS[app_config.plant_preset].0.to_string(),
+        Param::PlantDepth => app_config.plant_deep.to_string(),
+    }
+}
+
+// the index moved by the direction, around the end
+fn cycle(i: usize, len: usize, direction: f32) -> usize {
+    if direction > 0.0 {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }
+}
+
+// the rows and one more for the state of the building
+fn panel_rect(win: Rect) -> Rect {
+    let height = (PARAMS.len() + 1) as f32 * ROW_HEIGHT + PANEL_PAD;
+    Rect::from_x_y_w_h(
+        win.left() + PANEL_PAD + PANEL_WIDTH / 2.0,
+        win.top() - PANEL_PAD - height / 2.0,
+        PANEL_WIDTH,
+        height,
+    )
+}
+
+fn row_rect(win: Rect, i: usize) -> Rect {
+    let panel = panel_rect(win);
+    Rect::from_x_y_w_h(
+        panel.x(),
+        panel.top() - PANEL_PAD / 2.0 - (i as f32 + 0.5) * ROW_HEIGHT,
+        PANEL_WIDTH - PANEL_PAD,
+        ROW_HEIGHT,
+    )
+}
+
+fn minus_rect(row: Rect) -> Rect {
+    Rect::from_x_y_w_h(
+        row.right() - 2.5 * BUTTON_WIDTH,
+        row.y(),
+        BUTTON_WIDTH,
+        row.h() - 2.0,
+    )
+}
+
+fn plus_rect(row: Rect) -> Rect {
+    Rect::from_x_y_w_h(
+        row.right() - BUTTON_WIDTH / 2.0,
+        row.y(),
+        BUTTON_WIDTH,
+        row.h() - 2.0,
+    )
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use nannou::prelude::*;
+
+// the scrub bar on the bottom of the window
+const BAR_HEIGHT: f32 = 8.0;
+const BAR_PAD: f32 = 12.0;
+
+// how many seconds of the animation one press of an arrow key steps
+const STEP_SECONDS: f32 = 0.1;
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 16.0;
+
+// the controls of the growth animation:
+// space pauses and resumes, the arrows step, +/- change the speed, R restarts and the scrub bar
+// jumps to any moment of the animation
+pub struct Playback {
+    // the animation progress in dots, grows with the elapsed time
+    pub progress: f32,
+    pub dots_pro_second: f32,
+    pub speed: f32,
+    pub playing: bool,
+    // the progress, on which all trees are grown
+    pub duration: f32,
+    // the scrub bar is dragged with the mouse
+    scrubbing: bool,
+}
+
+impl Playback {
+    pub fn new(dots_pro_second: f32, duration: f32) -> Playback {
+        Playback {
+            progress: 0.0,
+            dots_pro_second,
+            speed: 1.0,
+            playing: true,
+            duration,
+            scrubbing: false,
+        }
+    }
+
+    pub fn update(&mut self, since_last: Duration) {
+        if self.playing && !self.scrubbing {
+            self.set_progress(
+                self.progress + since_last.as_secs_f32() * self.dots_pro_second * self.speed,
+            );
+        }
+    }
+
+    pub fn set_progress(&mut self, progress: f32) {
+        self.progress = progress.clamp(0.0, self.duration);
+    }
+
+    // the animation time in seconds at normal speed
+    pub fn time(&self) -> f32 {
+        self.progress / self.dots_pro_second
+    }
+
+    pub fn key_pressed(&mut self, key: Key) {
+        match key {
+            Key::Space => self.playing = !self.playing,
+            Key::Right => self.set_progress(self.progress + STEP_SECONDS * self.dots_pro_second),
+            Key::Left => self.set_progress(self.progress - STEP_SECONDS * self.dots_pro_second),
+            Key::Plus | Key::Equals | Key::NumpadAdd => {
+                self.speed = (self.speed * 2.0).min(MAX_SPEED)
+            }
+            Key::Minus | Key::NumpadSubtract => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            Key::R => {
+                self.progress = 0.0;
+                self.playing = true;
+            }
+            _ => {}
+        }
+    }
+
+    // returns true, if the click was on the scrub bar
+    pub fn mouse_pressed(&mut self, win: Rect, mouse: Point2) -> bool {
+        if bar_rect(win).pad(-BAR_PAD / 2.0).contains(mouse) {
+            self.scrubbing = true;
+            self.scrub(win, mouse);
+        }
+        self.scrubbing
+    }
+
+    pub fn mouse_moved(&mut self, win: Rect, mouse: Point2) {
+        if self.scrubbing {
+            self.scrub(win, mouse);
+        }
+    }
+
+    pub fn mouse_released(&mut self) {
+        self.scrubbing = false;
+    }
+
+    fn scrub(&mut self, win: Rect, mouse: Point2) {
+        let bar = bar_rect(win);
+        let fraction = ((mouse.x - bar.left()) / bar.w()).clamp(0.0, 1.0);
+        self.set_progress(fraction * self.duration);
+    }
+
+    // draws the scrub bar and the state of the playback over it
+    pub fn draw(&self, draw: &Draw, win: Rect, color: Rgb, info: &str) {
+        let bar = bar_rect(win);
+        let fraction = if self.duration > 0.0 {
+            self.progress / self.duration
+        } else {
+            1.0
+        };
+
+        draw.rect()
+            .xy(bar.xy())
+            .wh(bar.wh())
+            .color(rgba(0.0, 0.0, 0.0, 0.1));
+        draw.rect()
+            .x_y(bar.left() + bar.w() * fraction / 2.0, bar.y())
+            .w_h(bar.w() * fraction, bar.h())
+            .color(color);
+
+        let state = if self.playing { "playing" } else { "paused" };
+        let text = format!(
+            "{} x{}  {:.1}s / {:.1}s  {}",
+            state,
+            self.speed,
+            self.time(),
+            self.duration / self.dots_pro_second,
+            info
+        );
+        draw.text(&text)
+            .x_y(bar.x(), bar.top() + BAR_PAD)
+            .w(bar.w())
+            .font_size(12)
+            .left_justify()
+            .color(BLACK);
+    }
+}
+
+fn bar_rect(win: Rect) -> Rect {
+    Rect::from_x_y_w_h(
+        win.x(),
+        win.bottom() + BAR_PAD + BAR_HEIGHT / 2.0,
+        win.w() - 2.0 * BAR_PAD,
+        BAR_HEIGHT,
+    )
+}
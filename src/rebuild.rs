@@ -0,0 +1,153 @@
+use std::{
+    collections::VecDeque,
+    panic::{self, UnwindSafe},
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+};
+
+use crate::{camera::Projection, config::AppConfig, constants::MAX_ANIMATED_BRANCHES, SceneTree};
+
+// the work of the background thread
+enum Job {
+    // all trees of the config
+    Scene(AppConfig),
+    // the last planted tree of the config, that is added to the trees
+    Plant(AppConfig),
+}
+
+// the trees of the finished job
+pub enum Built {
+    Scene(Vec<SceneTree>),
+    Plant(Box<SceneTree>),
+}
+
+// builds the trees on one background thread, so the window doesn't freeze while a big tree is
+// built. The jobs are built one after the other, a new scene replaces the jobs, that wait before
+// it, and the results of the older scenes are dropped. The panic of the builder (like a wrong
+// grammar) comes back as the error
+pub struct Rebuilder {
+    jobs: Sender<(usize, Job, Projection)>,
+    receiver: Receiver<(usize, Result<Built, String>, Projection)>,
+    // the number of the last request
+    generation: usize,
+    // the number of the last scene request, the jobs before it are outdated
+    scene_generation: usize,
+    // the number of the last received job
+    done: usize,
+}
+
+impl Rebuilder {
+    pub fn new() -> Rebuilder {
+        let (jobs, job_receiver) = channel();
+        let (sender, receiver) = channel();
+        thread::spawn(move || work(job_receiver, sender));
+        Rebuilder {
+            jobs,
+            receiver,
+            generation: 0,
+            scene_generation: 0,
+            done: 0,
+        }
+    }
+
+    // starts to build all trees of the config
+    pub fn request(&mut self, app_config: &AppConfig, projection: Projection) {
+        self.generation += 1;
+        self.scene_generation = self.generation;
+        self.send(Job::Scene(app_config.clone()), projection);
+    }
+
+    // starts to build the last planted tree of the config
+    pub fn plant(&mut self, app_config: &AppConfig, projection: Projection) {
+        self.generation += 1;
+        self.send(Job::Plant(app_config.clone()), projection);
+    }
+
+    fn send(&mut self, job: Job, projection: Projection) {
+        // without the thread nothing is built, the viewer keeps the trees
+        let _ = self.jobs.send((self.generation, job, projection));
+    }
+
+    pub fn is_building(&self) -> bool {
+        self.done != self.generation
+    }
+
+    // the next built trees of the requests, that are not outdated, with the projection, by
+    // which they were projected
+    pub fn try_take(&mut self) -> Option<(Result<Built, String>, Projection)> {
+        while let Ok((generation, built, projection)) = self.receiver.try_recv() {
+            self.done = generation;
+            if generation >= self.scene_generation {
+                return Some((built, projection));
+            }
+        }
+        None
+    }
+}
+
+// the loop of the background thread, it ends with the viewer
+fn work(
+    jobs: Receiver<(usize, Job, Projection)>,
+    sender: Sender<(usize, Result<Built, String>, Projection)>,
+) {
+    let mut queue = VecDeque::new();
+    loop {
+        if queue.is_empty() {
+            match jobs.recv() {
+                Ok(job) => queue.push_back(job),
+                Err(_) => return,
+            }
+        }
+        queue.extend(jobs.try_iter());
+        // the last scene has everything, that was requested before it
+        if let Some(last_scene) = queue
+            .iter()
+            .rposition(|(_, job, _)| matches!(job, Job::Scene(_)))
+        {
+            queue.drain(..last_scene);
+        }
+
+        let Some((generation, job, projection)) = queue.pop_front() else {
+            continue;
+        };
+        let built = build_catching(|| build(&job, &projection));
+        if sender.send((generation, built, projection)).is_err() {
+            return;
+        }
+    }
+}
+
+fn build(job: &Job, projection: &Projection) -> Built {
+    match job {
+        Job::Scene(app_config) => Built::Scene(crate::scene_trees(
+            crate::build_trees_3d(app_config),
+            app_config,
+            projection,
+        )),
+        Job::Plant(app_config) => {
+            let i = app_config.planted.len() - 1;
+            let planted = &app_config.planted[i];
+            let tree_3d = crate::build_planted(app_config, planted, app_config.deeps.len() + i);
+            let max_branches =
+                MAX_ANIMATED_BRANCHES / (app_config.deeps.len() + app_config.planted.len());
+            Built::Plant(Box::new(crate::scene_tree(
+                tree_3d,
+                planted.start,
+                max_branches,
+                app_config,
+                projection,
+            )))
+        }
+    }
+}
+
+// runs the build and gives its panic as the error
+pub fn build_catching<T>(build: impl FnOnce() -> T + UnwindSafe) -> Result<T, String> {
+    panic::catch_unwind(build).map_err(|payload| {
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|text| text.to_string()))
+            .unwrap_or_else(|| "The trees could not be built".to_string())
+    })
+}
@@ -0,0 +1,49 @@
+use nannou::{
+    geom::Point2,
+    noise::{NoiseFn, Perlin},
+};
+
+use crate::lsystem::BranchDot;
+
+// how fast the gusts change in the time and in the space
+const GUST_SPEED: f64 = 0.7;
+const GUST_SCALE: f64 = 0.004;
+// on this distance from the root the branch is moved by the whole wind power
+const REFERENCE_LENGTH: f32 = 100.0;
+// the thinnest branch, so that the very thin branches don't fly away
+const MIN_THICKNESS: f32 = 0.5;
+
+// time varying wind, that bends the branches of the trees
+pub struct Wind {
+    power: Point2,
+    noise: Perlin,
+}
+
+impl Wind {
+    pub fn new(power: Point2) -> Wind {
+        Wind {
+            power,
+            noise: Perlin::new(),
+        }
+    }
+
+    // the offset of the dot at the time (in seconds), it grows with the distance from the root
+    // like on a bended stick and is smaller on the thick branches, the gusts come from the noise
+    pub fn offset(&self, time: f32, dot: &BranchDot) -> Point2 {
+        self.offset_at(time, dot.pos, dot.arc_length, dot.width)
+    }
+
+    // the offset of the place on a branch with the arc length from the root and the width
+    pub fn offset_at(&self, time: f32, pos: Point2, arc_length: f32, width: f32) -> Point2 {
+        let gust = self.noise.get([
+            time as f64 * GUST_SPEED,
+            pos.x as f64 * GUST_SCALE,
+            pos.y as f64 * GUST_SCALE,
+        ]) as f32;
+        // the gust is in -1..1, so the wind is between calm and double power
+        let strength = 1.0 + gust;
+        let bend = (arc_length / REFERENCE_LENGTH).powi(2);
+
+        self.power * strength * bend / width.max(MIN_THICKNESS)
+    }
+}